@@ -5,11 +5,14 @@ hint_before_comment = " "
 hint_before_multiline_comment_text = " "
 hint_before_oneline_comment_text = " "
 hint_table_constructor = " "
+-- hint_before_attrib = " "
+-- hint_inside_attrib = ""
 
 -- remove_comments = false
 remove_single_newlines = true
 -- remove_all_newlines = false
 remove_spaces_between_tokens = true
+-- remove_optional_spaces = false
 write_newline_at_eof = true
 write_newline_at_multiline_table = false
 write_newline_at_explist_multiline_table = true
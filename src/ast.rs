@@ -0,0 +1,45 @@
+//! The syntax tree of the formatter for the tools which need to parse Lua, e.g. linters. The tree keeps the spans of
+//! the nodes and the gaps between the tokens, so the source can be edited at the positions of the nodes.
+//!
+//! ```
+//! use luafmt::ast::{self, Dialect, Node, Visitor};
+//!
+//! struct Names(Vec<String>);
+//!
+//! impl Visitor for Names {
+//!     fn visit_node(&mut self, node: &Node) {
+//!         if let Node::Name(_, name) = node {
+//!             self.0.push(name.clone());
+//!         }
+//!         ast::walk(self, node);
+//!     }
+//! }
+//!
+//! let tree = ast::parse("local a = b + c", Dialect::Lua54).unwrap();
+//! let mut names = Names(Vec::new());
+//! names.visit_node(&tree);
+//! assert_eq!(names.0, ["a", "b", "c"]);
+//! ```
+
+pub use crate::parser::common::{Loc, Str};
+pub use crate::parser::lua_ast::{FieldsOpts, Node, Part, TableConstructorOpts};
+pub use crate::parser::visitor::{walk, walk_mut, Children, ChildrenMut, Visitor, VisitorMut};
+pub use crate::parser::{Dialect, SyntaxError, Token};
+
+use crate::parser;
+
+/// Parses the source, the error is reported at the first token which cannot be parsed
+pub fn parse(src: &str, dialect: Dialect) -> Result<Node, Box<SyntaxError>> {
+    parser::parse_lua_with_dialect(src, dialect).map_err(|err| Box::new(SyntaxError::from_parse_error(src, err)))
+}
+
+/// Parses the source keeping every top-level statement which fails to parse as `Node::Unparsed`, also returns the
+/// errors of these statements. Lexical errors are not recovered.
+pub fn parse_with_recovery(src: &str, dialect: Dialect) -> Result<(Node, Vec<SyntaxError>), Box<SyntaxError>> {
+    match parser::parse_lua_with_recovery(src, dialect) {
+        Ok((node, errors)) => {
+            Ok((node, errors.into_iter().map(|err| SyntaxError::from_parse_error(src, err)).collect()))
+        }
+        Err(err) => Err(Box::new(SyntaxError::from_parse_error(src, err))),
+    }
+}
@@ -0,0 +1,55 @@
+use std::env;
+use std::io;
+
+use luafmt::config::Config;
+
+mod server;
+#[cfg(test)]
+mod server_test;
+
+fn parse_options(options: &[String]) -> Config {
+    let mut config = Config::default();
+
+    for option in options {
+        let mut parts = option.trim_start_matches('-').splitn(2, '=');
+        match (option.starts_with("--"), parts.next(), parts.next()) {
+            (true, Some(name), Some(value)) => config.set(name, value),
+            _ => eprintln!("Unrecognized option `{}`", option),
+        }
+    }
+
+    config
+}
+
+// Usage: luafmt-lsp [configuration options]
+// The server speaks LSP over stdin/stdout. The configuration options are applied to every document, they override the
+// options of the `.luafmt*.lua` configuration files of the document.
+fn main() {
+    let options: Vec<String> = env::args().skip(1).collect();
+    let config = parse_options(&options);
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let exit_code = match server::Server::new(config).run(&mut stdin.lock(), &mut stdout.lock()) {
+        Ok(exit_code) => exit_code,
+        Err(err) => {
+            eprintln!("An error occured while serving the client: {}", err);
+            1
+        }
+    };
+
+    std::process::exit(exit_code);
+}
+
+#[test]
+fn test_parse_options() {
+    use luafmt::config::FormatOpts;
+
+    let options = vec!["--field_separator=,".to_string(), "--error_recovery=true".to_string()];
+    let cfg = Config {
+        error_recovery: Some(true),
+        fmt: FormatOpts { field_separator: Some(",".to_string()), ..FormatOpts::default() },
+        ..Config::default()
+    };
+    assert_eq!(parse_options(&options), cfg);
+}
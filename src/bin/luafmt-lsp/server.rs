@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use luafmt::config::Config;
+use luafmt::formatter;
+use luafmt::json::Json;
+use luafmt::Dialect;
+
+// error codes of JSON-RPC and LSP
+const PARSE_ERROR: f64 = -32700.0;
+const INVALID_REQUEST: f64 = -32600.0;
+const METHOD_NOT_FOUND: f64 = -32601.0;
+const INVALID_PARAMS: f64 = -32602.0;
+const SERVER_NOT_INITIALIZED: f64 = -32002.0;
+const REQUEST_FAILED: f64 = -32803.0;
+
+// the documents are always sent as a whole
+const TEXT_DOCUMENT_SYNC_FULL: f64 = 1.0;
+
+// the maximum size of the message content, the bigger messages are skipped
+const MAX_MESSAGE_LEN: usize = 64 << 20;
+
+type RequestError = (f64, String);
+
+/// The content of a message, or the error of the message which has been skipped
+pub type Content = Result<String, RequestError>;
+
+fn object(members: Vec<(&str, Json)>) -> Json {
+    Json::Object(members.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+}
+
+/// Reads the content of the next message, returns `None` at the end of input
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Content>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        let mut parts = header.splitn(2, ':');
+        if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<u64>().ok();
+            }
+        }
+    }
+
+    let content_length = match content_length {
+        Some(length) => length,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidData, "missing `Content-Length` header")),
+    };
+    if content_length > MAX_MESSAGE_LEN as u64 {
+        io::copy(&mut io::Read::take(&mut *reader, content_length), &mut io::sink())?;
+        let msg = format!("message of {} bytes exceeds the limit of {} bytes", content_length, MAX_MESSAGE_LEN);
+        return Ok(Some(Err((INVALID_REQUEST, msg))));
+    }
+
+    let mut content = vec![0; content_length as usize];
+    reader.read_exact(&mut content)?;
+    match String::from_utf8(content) {
+        Ok(content) => Ok(Some(Ok(content))),
+        Err(_) => Ok(Some(Err((PARSE_ERROR, "message is not valid UTF-8".to_string())))),
+    }
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Json) -> io::Result<()> {
+    let content = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    writer.flush()
+}
+
+// positions are counted in UTF-16 code units, `line_starts` are the byte offsets of the lines of the text
+fn position(text: &str, line_starts: &[usize], offset: usize) -> Json {
+    let line = line_starts.partition_point(|&start| start <= offset) - 1;
+    let character = text[line_starts[line]..offset].encode_utf16().count();
+
+    object(vec![("line", Json::Number(line as f64)), ("character", Json::Number(character as f64))])
+}
+
+/// Returns the text edits which turn the `text` into the `output`, only the changed parts between the tokens of the
+/// `dialect` are replaced
+pub fn text_edits(text: &str, output: &str, dialect: Dialect) -> Vec<Json> {
+    let chars_to_bytes: Vec<usize> = text.char_indices().map(|(b, _)| b).chain(std::iter::once(text.len())).collect();
+    let line_starts: Vec<usize> = std::iter::once(0).chain(text.match_indices('\n').map(|(b, _)| b + 1)).collect();
+
+    formatter::text_edits(text, output, dialect)
+        .into_iter()
+        .map(|(chars, new_text)| {
+            let range = object(vec![
+                ("start", position(text, &line_starts, chars_to_bytes[chars.start])),
+                ("end", position(text, &line_starts, chars_to_bytes[chars.end])),
+            ]);
+            object(vec![("range", range), ("newText", Json::String(new_text))])
+        })
+        .collect()
+}
+
+fn decode_percents(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = s.get(i + 1..i + 3)?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Returns the file path of the `file://` URI
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = decode_percents(uri.strip_prefix("file://")?)?;
+
+    // `file:///C:/dir` on Windows
+    let path = match path.get(2..3) {
+        Some(":") if cfg!(windows) => path[1..].to_string(),
+        _ => path,
+    };
+
+    Some(PathBuf::from(path))
+}
+
+pub struct Server {
+    config: Config,
+    documents: HashMap<String, String>,
+    initialized: bool,
+    shutdown: bool,
+    exit_code: Option<i32>,
+}
+
+impl Server {
+    pub fn new(config: Config) -> Self {
+        Server { config, documents: HashMap::new(), initialized: false, shutdown: false, exit_code: None }
+    }
+
+    /// Serves the messages till the `exit` notification, returns the exit code of the server
+    pub fn run(&mut self, reader: &mut impl BufRead, writer: &mut impl Write) -> io::Result<i32> {
+        while let Some(content) = read_message(reader)? {
+            let response = match content {
+                Ok(content) => self.handle_message(&content),
+                // the id of the skipped message is unknown
+                Err(err) => Some(Self::response(Json::Null, Err(err))),
+            };
+            if let Some(response) = response {
+                write_message(writer, &response)?;
+            }
+
+            if let Some(exit_code) = self.exit_code {
+                return Ok(exit_code);
+            }
+        }
+
+        // the client has gone without the `exit` notification
+        Ok(1)
+    }
+
+    /// Returns the response to the message, notifications have no response
+    pub fn handle_message(&mut self, content: &str) -> Option<Json> {
+        let message = match Json::parse(content) {
+            Ok(message) => message,
+            Err(err) => return Some(Self::response(Json::Null, Err((PARSE_ERROR, err)))),
+        };
+
+        let method = message.get("method").and_then(Json::as_str);
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+
+        match (message.get("id"), method) {
+            (Some(id), Some(method)) => Some(Self::response(id.clone(), self.handle_request(method, &params))),
+            (None, Some(method)) => {
+                self.handle_notification(method, &params);
+                None
+            }
+            // responses to the server requests are not expected
+            (Some(_), None) => None,
+            (None, None) => Some(Self::response(Json::Null, Err((INVALID_REQUEST, "invalid message".to_string())))),
+        }
+    }
+
+    fn response(id: Json, result: Result<Json, RequestError>) -> Json {
+        let mut members = vec![("jsonrpc", Json::String("2.0".to_string())), ("id", id)];
+        match result {
+            Ok(result) => members.push(("result", result)),
+            Err((code, message)) => {
+                members.push(("error", object(vec![("code", Json::Number(code)), ("message", Json::String(message))])))
+            }
+        }
+        object(members)
+    }
+
+    fn handle_request(&mut self, method: &str, params: &Json) -> Result<Json, RequestError> {
+        match method {
+            "initialize" => {
+                self.initialized = true;
+                return Ok(Self::capabilities());
+            }
+            _ if !self.initialized => return Err((SERVER_NOT_INITIALIZED, "server is not initialized".to_string())),
+            _ if self.shutdown => return Err((INVALID_REQUEST, "server is shutting down".to_string())),
+            _ => {}
+        }
+
+        let uri = params.get("textDocument").and_then(|doc| doc.get("uri")).and_then(Json::as_str);
+        let line = |key: &str| params.get(key).and_then(|pos| pos.get("line")).and_then(Json::as_usize);
+        let invalid_params = || (INVALID_PARAMS, format!("invalid `{}` params", method));
+
+        match method {
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/formatting" => self.format(uri.ok_or_else(invalid_params)?, None),
+            "textDocument/rangeFormatting" => {
+                let range = params.get("range").ok_or_else(invalid_params)?;
+                let start = range.get("start").and_then(|pos| pos.get("line")).and_then(Json::as_usize);
+                let end = range.get("end").ok_or_else(invalid_params)?;
+                let (end_line, end_char) =
+                    (end.get("line").and_then(Json::as_usize), end.get("character").and_then(Json::as_usize));
+
+                match (uri, start, end_line, end_char) {
+                    (Some(uri), Some(start), Some(end_line), Some(end_char)) => {
+                        // the range which ends at the start of a line does not include this line
+                        let end_line = match end_char == 0 && end_line > start {
+                            true => end_line - 1,
+                            false => end_line,
+                        };
+                        self.format(uri, Some((start + 1, end_line + 1)))
+                    }
+                    _ => Err(invalid_params()),
+                }
+            }
+            "textDocument/onTypeFormatting" => match (uri, line("position")) {
+                // the line which has just been finished is formatted
+                (Some(_), Some(0)) => Ok(Json::Array(Vec::new())),
+                (Some(uri), Some(line)) => self.format(uri, Some((line, line))).or(Ok(Json::Null)),
+                _ => Err(invalid_params()),
+            },
+            _ => Err((METHOD_NOT_FOUND, format!("unsupported method `{}`", method))),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Json) {
+        let document = params.get("textDocument");
+        let uri = document.and_then(|doc| doc.get("uri")).and_then(Json::as_str).map(str::to_string);
+
+        match (method, uri) {
+            ("exit", _) => self.exit_code = Some(if self.shutdown { 0 } else { 1 }),
+            ("textDocument/didOpen", Some(uri)) => {
+                if let Some(text) = document.and_then(|doc| doc.get("text")).and_then(Json::as_str) {
+                    self.documents.insert(uri, text.to_string());
+                }
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                let changes = params.get("contentChanges").and_then(Json::as_array);
+                if let Some(text) = changes.and_then(|v| v.last()).and_then(|c| c.get("text")).and_then(Json::as_str) {
+                    self.documents.insert(uri, text.to_string());
+                }
+            }
+            ("textDocument/didClose", Some(uri)) => {
+                self.documents.remove(&uri);
+            }
+            _ => {}
+        }
+    }
+
+    fn capabilities() -> Json {
+        let on_type = object(vec![("firstTriggerCharacter", Json::String("\n".to_string()))]);
+        let capabilities = object(vec![
+            ("textDocumentSync", Json::Number(TEXT_DOCUMENT_SYNC_FULL)),
+            ("documentFormattingProvider", Json::Bool(true)),
+            ("documentRangeFormattingProvider", Json::Bool(true)),
+            ("documentOnTypeFormattingProvider", on_type),
+        ]);
+        let server_info = object(vec![
+            ("name", Json::String("luafmt-lsp".to_string())),
+            ("version", Json::String(env!("CARGO_PKG_VERSION").to_string())),
+        ]);
+
+        object(vec![("capabilities", capabilities), ("serverInfo", server_info)])
+    }
+
+    fn format(&self, uri: &str, line_range: Option<(usize, usize)>) -> Result<Json, RequestError> {
+        let text = match self.documents.get(uri) {
+            Some(text) => text,
+            None => return Err((INVALID_PARAMS, format!("document `{}` is not opened", uri))),
+        };
+
+        let cfg = Config { line_range, ..self.config.clone() };
+        let result = match uri_to_path(uri) {
+            Some(path) => formatter::process_buffer_of_file_with_dialect(text, &path, &cfg, false),
+            None => formatter::process_buffer_with_config(text, &cfg, false).map(|output| (output, cfg.dialect())),
+        };
+
+        match result {
+            Ok((output, dialect)) => Ok(Json::Array(text_edits(text, &output, dialect))),
+            Err(err) => Err((REQUEST_FAILED, err.to_string())),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use super::server::*;
+use luafmt::config::*;
+use luafmt::json::Json;
+use luafmt::Dialect;
+
+fn message(content: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", content.len(), content)
+}
+
+fn test_config() -> Config {
+    Config {
+        fmt: FormatOpts {
+            replace_zero_spaces_with_hint: Some(true),
+            remove_spaces_between_tokens: Some(true),
+            ..FormatOpts::default()
+        },
+        ..Config::default()
+    }
+}
+
+fn responses(output: &[u8]) -> Vec<String> {
+    let mut reader = output;
+    let mut result = Vec::new();
+    while let Some(content) = read_message(&mut reader).unwrap() {
+        result.push(content.unwrap());
+    }
+    result
+}
+
+#[test]
+fn test_session() {
+    let input = [
+        message(r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/formatting","params":{}}"#),
+        message(r#"{"jsonrpc":"2.0","id":2,"method":"initialize","params":{"capabilities":{}}}"#),
+        message(r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#),
+        message(
+            r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"untitled:1","languageId":"lua","version":1,"text":"a=1\nlocal  b  =  {1,2}\nc=3\n"}}}"#,
+        ),
+        message(
+            r#"{"jsonrpc":"2.0","id":3,"method":"textDocument/formatting","params":{"textDocument":{"uri":"untitled:1"},"options":{"tabSize":4,"insertSpaces":true}}}"#,
+        ),
+        message(r#"{"jsonrpc":"2.0","id":"4","method":"shutdown"}"#),
+        message(r#"{"jsonrpc":"2.0","method":"exit"}"#),
+    ]
+    .concat();
+
+    let mut output = Vec::new();
+    let exit_code = Server::new(test_config()).run(&mut input.as_bytes(), &mut output).unwrap();
+    assert_eq!(exit_code, 0);
+
+    let responses = responses(&output);
+    assert_eq!(responses.len(), 4);
+    assert_eq!(
+        responses[0],
+        r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32002,"message":"server is not initialized"}}"#
+    );
+
+    let capabilities = Json::parse(&responses[1]).unwrap();
+    let capabilities = capabilities.get("result").and_then(|r| r.get("capabilities")).unwrap();
+    assert_eq!(capabilities.get("documentFormattingProvider"), Some(&Json::Bool(true)));
+    assert_eq!(capabilities.get("documentRangeFormattingProvider"), Some(&Json::Bool(true)));
+
+    let edits = Json::parse(&responses[2]).unwrap();
+    let edits = edits.get("result").and_then(Json::as_array).unwrap();
+    assert_eq!(edits.len(), 8);
+    assert_eq!(
+        edits[5].to_string(),
+        r#"{"range":{"start":{"line":1,"character":16},"end":{"line":1,"character":16}},"newText":" "}"#
+    );
+    assert_eq!(responses[3], r#"{"jsonrpc":"2.0","id":"4","result":null}"#);
+}
+
+#[test]
+fn test_invalid_messages() {
+    let nested = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"shutdown","params":{}}}"#, "[".repeat(100_000));
+    let input =
+        [message(&nested).as_bytes(), b"Content-Length: 2\r\n\r\n\"\xff", b"Content-Length: 100000000000\r\n\r\n{}"]
+            .concat();
+
+    let mut output = Vec::new();
+    let exit_code = Server::new(test_config()).run(&mut input.as_slice(), &mut output).unwrap();
+    assert_eq!(exit_code, 1);
+
+    let responses = responses(&output);
+    assert_eq!(responses.len(), 3);
+    assert_eq!(
+        responses[0],
+        r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"the nesting is deeper than 128 levels at line 1"}}"#
+    );
+    assert_eq!(
+        responses[1],
+        r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"message is not valid UTF-8"}}"#
+    );
+    assert_eq!(
+        responses[2],
+        r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"message of 100000000000 bytes exceeds the limit of 67108864 bytes"}}"#
+    );
+}
+
+#[test]
+fn test_range_formatting() {
+    let mut server = Server::new(test_config());
+    server.handle_message(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#);
+    server.handle_message(
+        r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"untitled:1","text":"a=1\nb=2\nc=3"}}}"#,
+    );
+
+    let response = server.handle_message(
+        r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/rangeFormatting","params":{"textDocument":{"uri":"untitled:1"},"range":{"start":{"line":1,"character":0},"end":{"line":2,"character":0}}}}"#,
+    );
+    assert_eq!(
+        response.unwrap().to_string(),
+        r#"{"jsonrpc":"2.0","id":2,"result":[{"range":{"start":{"line":1,"character":1},"end":{"line":1,"character":1}},"newText":" "},{"range":{"start":{"line":1,"character":2},"end":{"line":1,"character":2}},"newText":" "}]}"#
+    );
+
+    // the line before the cursor is formatted
+    server.handle_message(
+        r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":{"uri":"untitled:1","version":2},"contentChanges":[{"text":"a=1\nb=2\nc=3\n"}]}}"#,
+    );
+    let response = server.handle_message(
+        r#"{"jsonrpc":"2.0","id":3,"method":"textDocument/onTypeFormatting","params":{"textDocument":{"uri":"untitled:1"},"position":{"line":3,"character":0},"ch":"\n","options":{}}}"#,
+    );
+    assert_eq!(
+        response.unwrap().to_string(),
+        r#"{"jsonrpc":"2.0","id":3,"result":[{"range":{"start":{"line":2,"character":1},"end":{"line":2,"character":1}},"newText":" "},{"range":{"start":{"line":2,"character":2},"end":{"line":2,"character":2}},"newText":" "}]}"#
+    );
+
+    // syntax errors are reported
+    server.handle_message(
+        r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":{"uri":"untitled:1","version":3},"contentChanges":[{"text":"a = = 1"}]}}"#,
+    );
+    let response = server.handle_message(
+        r#"{"jsonrpc":"2.0","id":4,"method":"textDocument/formatting","params":{"textDocument":{"uri":"untitled:1"}}}"#,
+    );
+    let response = response.unwrap();
+    let error = response.get("error").unwrap();
+    assert_eq!(error.get("code"), Some(&Json::Number(-32803.0)));
+    assert!(error.get("message").and_then(Json::as_str).unwrap().starts_with("parsing error: unexpected token `=`"));
+
+    let response = server.handle_message(r#"{"jsonrpc":"2.0","id":5,"method":"textDocument/hover","params":{}}"#);
+    assert_eq!(
+        response.unwrap().to_string(),
+        r#"{"jsonrpc":"2.0","id":5,"error":{"code":-32601,"message":"unsupported method `textDocument/hover`"}}"#
+    );
+}
+
+#[test]
+fn test_text_edits() {
+    assert_eq!(text_edits("a\nb\n", "a\nb\n", Dialect::Lua54), vec![]);
+
+    // the characters out of the basic plane take two code units
+    assert_eq!(
+        Json::Array(text_edits("x = 'ё😀' y=1", "x = 'ё😀'\ny = 1", Dialect::Lua54)).to_string(),
+        r#"[{"range":{"start":{"line":0,"character":9},"end":{"line":0,"character":10}},"newText":"\n"},{"range":{"start":{"line":0,"character":11},"end":{"line":0,"character":11}},"newText":" "},{"range":{"start":{"line":0,"character":12},"end":{"line":0,"character":12}},"newText":" "}]"#
+    );
+
+    // the lines of a large text are found without rescanning it for every edit
+    let edits = text_edits(&"a=1\n".repeat(50_000), &"a = 1\n".repeat(50_000), Dialect::Lua54);
+    assert_eq!(edits.len(), 100_000);
+    assert_eq!(
+        edits[99_999].to_string(),
+        r#"{"range":{"start":{"line":49999,"character":2},"end":{"line":49999,"character":2}},"newText":" "}"#
+    );
+}
+
+#[test]
+fn test_uri_to_path() {
+    use std::path::PathBuf;
+
+    assert_eq!(uri_to_path("file:///home/user/my%20file.lua"), Some(PathBuf::from("/home/user/my file.lua")));
+    assert_eq!(uri_to_path("file:///tmp/%D1%84.lua"), Some(PathBuf::from("/tmp/ф.lua")));
+    assert_eq!(uri_to_path("untitled:1"), None);
+    assert_eq!(uri_to_path("file:///tmp/%zz.lua"), None);
+}
@@ -1,11 +1,15 @@
 use regex::Regex;
 use std::fmt;
 use std::fmt::Debug;
+use std::ffi::OsStr;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 
+use crate::file_util;
+use crate::glob::Glob;
 use crate::parser;
+use crate::style;
 
 #[macro_export]
 macro_rules! cfg_write_helper {
@@ -29,376 +33,971 @@ pub trait ConfiguredWrite {
     fn configured_write(&self, f: &mut String, config: &Config, buf: &str, state: &mut State) -> std::fmt::Result;
 }
 
+/// The options which are applied to the files matching one of the globs, the globs are relative to the directory of
+/// the configuration file
 #[derive(Debug, PartialEq, Clone)]
-pub struct Config {
-    // hint
-    pub line_range: Option<(usize, usize)>,
-    pub fmt: FormatOpts,
+pub struct ConfigOverride {
+    pub files: Vec<Glob>,
+    pub base: PathBuf,
+    pub config: Config,
 }
 
+/// Group of the options in the documentation
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Category {
+    General,
+    Hint,
+    RemoveWrite,
+    Indentation,
+    Format,
+    Other,
+    SingleLine,
+}
+
+impl Category {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Category::General => "general",
+            Category::Hint => "hints",
+            Category::RemoveWrite => "remove and write",
+            Category::Indentation => "indentation",
+            Category::Format => "line breaks",
+            Category::Other => "other",
+            Category::SingleLine => "single line",
+        }
+    }
+}
+
+/// Values which are accepted by an option in addition to its type
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Domain {
+    Any,
+    Positive,
+    Integers(&'static [usize]),
+    Strings(&'static [&'static str]),
+}
+
+impl Domain {
+    /// Returns the description of the values, e.g. `1, 2`
+    pub fn describe(&self, type_name: &str) -> String {
+        match self {
+            Domain::Any if type_name == "boolean" => "true, false".to_string(),
+            Domain::Any => format!("any {}", type_name),
+            Domain::Positive => "positive integer".to_string(),
+            Domain::Integers(values) => values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(", "),
+            Domain::Strings(values) => values.iter().map(|value| format!("{:?}", value)).collect::<Vec<_>>().join(", "),
+        }
+    }
+
+    fn contains(&self, value: &OptionValue) -> bool {
+        match (self, value) {
+            (Domain::Positive, OptionValue::Integer(value)) => *value > 0,
+            (Domain::Integers(values), OptionValue::Integer(value)) => values.iter().any(|v| *v as i64 == *value),
+            (Domain::Strings(values), OptionValue::String(value)) => values.contains(&value.as_str()),
+            _ => true,
+        }
+    }
+}
+
+/// Description of an option, the registry of the options drives the configuration files, the command line and the
+/// documentation
 #[derive(Debug, PartialEq, Clone)]
-pub struct FormatOpts {
-    // hint
-    pub replace_zero_spaces_with_hint: Option<bool>,
-    pub hint_after_multiline_comment: Option<String>,
-    pub hint_after_multiline_comment_text: Option<String>,
-    pub hint_before_comment: Option<String>,
-    pub hint_before_multiline_comment_text: Option<String>,
-    pub hint_before_oneline_comment_text: Option<String>,
-    pub hint_table_constructor: Option<String>,
-
-    pub remove_comments: Option<bool>,
-    pub remove_single_newlines: Option<bool>,
-    pub remove_all_newlines: Option<bool>,
-    pub remove_spaces_between_tokens: Option<bool>,
-    pub write_newline_at_eof: Option<bool>,
-    pub write_newline_at_multiline_table: Option<bool>,
-    pub write_newline_at_explist_multiline_table: Option<bool>,
-
-    // indentation
-    pub indentation_string: Option<String>,
-    pub indent_var_suffix: Option<bool>,
-    pub indent_one_line_var_suffix: Option<bool>,
-    pub indent_exp_list: Option<bool>,
-    pub indent_one_line_exp_list: Option<bool>,
-
-    // format
-    pub newline_format_first_oneline_comment: Option<usize>,
-    pub newline_format_first_multiline_comment: Option<usize>,
-    pub newline_format_oneline_comment: Option<usize>,
-    pub newline_format_multiline_comment: Option<usize>,
-    pub newline_format_statement: Option<usize>,
-    pub newline_format_do_end: Option<usize>,
-    pub newline_format_for: Option<usize>,
-    pub newline_format_function: Option<usize>,
-    pub newline_format_if: Option<usize>,
-    pub newline_format_repeat_until: Option<usize>,
-    pub newline_format_table_constructor: Option<usize>,
-    pub newline_format_table_field: Option<usize>,
-    pub newline_format_while: Option<usize>,
-    pub newline_format_binary_op: Option<usize>,
-    pub newline_format_var_suffix: Option<usize>,
-    pub newline_format_exp_list: Option<usize>,
-    pub newline_format_exp_list_first: Option<usize>,
-
-    // other
-    // replace_tabs_with_spaces: Option<String>,
+pub struct OptionInfo {
+    pub name: &'static str,
+    pub type_name: &'static str,
+    pub category: Category,
+    pub domain: Domain,
+    pub description: &'static str,
+}
+
+// sets the typed value of the option if it belongs to the domain
+fn set_option<T: OptionType>(
+    field: &mut Option<T>, name: &str, value: &OptionValue, domain: &Domain,
+) -> Result<(), String> {
+    let typed = T::from_option_value(name, value)?;
+    let value = typed.to_option_value();
+    if !domain.contains(&value) {
+        let value_str = match &value {
+            OptionValue::String(s) => s.clone(),
+            value => value.to_lua(),
+        };
+        let expected = match domain {
+            Domain::Positive => domain.describe(T::TYPE_NAME),
+            _ => format!("one of: {}", domain.describe(T::TYPE_NAME)),
+        };
+        return Err(format!("Invalid `{}` option value `{}`, expected {}", name, value_str, expected));
+    }
+    *field = Some(typed);
+    Ok(())
+}
+
+// Declares the format options, every option is a line of `name: type, category, domain, description`. The struct,
+// its default, merging, setting, printing and documentation are generated from the declarations.
+macro_rules! format_options {
+    ($($name:ident: $type:ty, $category:ident, $domain:expr, $description:literal;)+) => {
+        #[derive(Debug, PartialEq, Clone)]
+        pub struct FormatOpts {
+            $( pub $name: Option<$type>, )+
+        }
+
+        impl FormatOpts {
+            pub const fn default() -> Self {
+                FormatOpts { $( $name: None, )+ }
+            }
+
+            /// Overrides the options which are set in `other`
+            pub fn merge(&mut self, other: &FormatOpts) {
+                $( if other.$name.is_some() {
+                    self.$name = other.$name.clone();
+                } )+
+            }
+
+            /// Returns the values of the options which are set
+            pub fn option_values(&self) -> Vec<(&'static str, OptionValue)> {
+                let mut values = Vec::new();
+                $( if let Some(value) = &self.$name {
+                    values.push((stringify!($name), value.to_option_value()));
+                } )+
+                values
+            }
+
+            // returns `None` if there is no format option with the name
+            fn try_set(&mut self, option_name: &str, value: &OptionValue) -> Option<Result<(), String>> {
+                $( if option_name == stringify!($name) {
+                    return Some(set_option(&mut self.$name, option_name, value, &$domain));
+                } )+
+                None
+            }
+        }
+
+        /// The registry of the format options
+        pub static FORMAT_OPTIONS: &[OptionInfo] = &[
+            $( OptionInfo {
+                name: stringify!($name),
+                type_name: <$type as OptionType>::TYPE_NAME,
+                category: Category::$category,
+                domain: $domain,
+                description: $description,
+            }, )+
+        ];
+    };
+}
+
+const NEWLINE_DOMAIN: Domain = Domain::Integers(&[1]);
+const STYLE_DOMAIN: Domain = Domain::Strings(&style::STYLES);
+const LUA_VERSION_DOMAIN: Domain = Domain::Strings(&["5.1", "5.2", "5.3", "5.4", "luajit", "luau"]);
+
+format_options! {
+    replace_zero_spaces_with_hint: bool, Hint, Domain::Any,
+        "Write the hint where the source has no space between tokens";
+    hint_after_multiline_comment: String, Hint, Domain::Any, "Text after a multiline comment";
+    hint_after_multiline_comment_text: String, Hint, Domain::Any,
+        "Text between the text and the closing brackets of a multiline comment";
+    hint_before_comment: String, Hint, Domain::Any, "Text before a comment";
+    hint_before_multiline_comment_text: String, Hint, Domain::Any,
+        "Text between the opening brackets and the text of a multiline comment";
+    hint_before_oneline_comment_text: String, Hint, Domain::Any, "Text between `--` and the text of a one-line comment";
+    hint_table_constructor: String, Hint, Domain::Any, "Text inside the braces of a table constructor";
+    hint_before_attrib: String, Hint, Domain::Any,
+        "Text between a local variable and its attribute (a space if not set)";
+    hint_inside_attrib: String, Hint, Domain::Any, "Text inside the angle brackets of an attribute";
+
+    remove_comments: bool, RemoveWrite, Domain::Any, "Remove all comments";
+    remove_single_newlines: bool, RemoveWrite, Domain::Any,
+        "Remove the line breaks of the source which do not make an empty line";
+    remove_all_newlines: bool, RemoveWrite, Domain::Any, "Remove all line breaks of the source";
+    remove_spaces_between_tokens: bool, RemoveWrite, Domain::Any,
+        "Remove the spaces of the source between tokens, the hints are written instead";
+    remove_optional_spaces: bool, RemoveWrite, Domain::Any,
+        "Write the space hints only between the tokens which would merge without a space";
+    write_newline_at_eof: bool, RemoveWrite, Domain::Any, "End the file with a line break";
+    write_newline_at_multiline_table: bool, RemoveWrite, Domain::Any,
+        "Start a multiline table constructor on a new line";
+    write_newline_at_explist_multiline_table: bool, RemoveWrite, Domain::Any,
+        "Start a multiline table constructor of an expression list on a new line";
+
+    indentation_string: String, Indentation, Domain::Any, "Text of one indentation level";
+    indent_var_suffix: bool, Indentation, Domain::Any,
+        "Indent the suffixes of a variable (fields, indices, calls) placed on new lines";
+    indent_one_line_var_suffix: bool, Indentation, Domain::Any,
+        "Indent the suffixes of a variable even if they fit on a single line";
+    indent_exp_list: bool, Indentation, Domain::Any, "Indent the expressions of a list placed on new lines";
+    indent_one_line_exp_list: bool, Indentation, Domain::Any,
+        "Indent the expressions of a list even if they fit on a single line";
+
+    newline_format_first_oneline_comment: usize, Format, NEWLINE_DOMAIN,
+        "Put the one-line comment at the start of a block on a new line";
+    newline_format_first_multiline_comment: usize, Format, NEWLINE_DOMAIN,
+        "Put the multiline comment at the start of a block on a new line";
+    newline_format_oneline_comment: usize, Format, NEWLINE_DOMAIN, "Put a one-line comment on a new line";
+    newline_format_multiline_comment: usize, Format, NEWLINE_DOMAIN, "Put a multiline comment on a new line";
+    newline_format_statement: usize, Format, NEWLINE_DOMAIN, "Put every statement on a new line";
+    newline_format_do_end: usize, Format, NEWLINE_DOMAIN, "Put the body of a `do ... end` block on new lines";
+    newline_format_for: usize, Format, NEWLINE_DOMAIN, "Put the body of a `for` loop on new lines";
+    newline_format_function: usize, Format, NEWLINE_DOMAIN, "Put the body of a function on new lines";
+    newline_format_if: usize, Format, NEWLINE_DOMAIN, "Put the branches of an `if` statement on new lines";
+    newline_format_repeat_until: usize, Format, NEWLINE_DOMAIN,
+        "Put the body of a `repeat ... until` loop on new lines";
+    newline_format_table_constructor: usize, Format, NEWLINE_DOMAIN,
+        "Put the closing brace of a multiline table constructor on a new line";
+    newline_format_table_field: usize, Format, NEWLINE_DOMAIN,
+        "Put every field of a multiline table constructor on a new line";
+    newline_format_while: usize, Format, NEWLINE_DOMAIN, "Put the body of a `while` loop on new lines";
+    newline_format_binary_op: usize, Format, Domain::Integers(&[1, 2]),
+        "Break the long binary operations before (1) or after (2) the operators";
+    newline_format_var_suffix: usize, Format, NEWLINE_DOMAIN,
+        "Break the long chains of variable suffixes before the suffixes";
+    newline_format_exp_list: usize, Format, NEWLINE_DOMAIN, "Break the long expression lists after the commas";
+    newline_format_exp_list_first: usize, Format, NEWLINE_DOMAIN,
+        "Break the long expression lists before the first expression";
+
+    // replace_tabs_with_spaces: String
     // tabs_as_spaces_count
-    pub field_separator: Option<String>,
-    pub write_trailing_field_separator: Option<bool>,
-    pub convert_charstring_to_normalstring: Option<bool>,
-
-    // oneline
-    pub max_width: Option<usize>,
-    pub force_single_line_binary_op: Option<bool>,
-    pub force_single_line_table: Option<bool>,
-    pub force_single_line_iv_table_field: Option<bool>,
-    pub force_single_line_kv_table_field: Option<bool>,
-    pub force_single_line_if: Option<bool>,
-    pub force_single_line_top_level_function: Option<bool>,
-    pub force_single_line_scoped_function: Option<bool>,
-    pub force_single_line_var_suffix: Option<bool>,
-    pub force_single_line_exp_list: Option<bool>,
-}
-
-impl FormatOpts {
-    pub const fn default() -> Self {
-        FormatOpts {
-            // hint
-            replace_zero_spaces_with_hint: None,
-            hint_after_multiline_comment: None,
-            hint_after_multiline_comment_text: None,
-            hint_before_comment: None,
-            hint_before_multiline_comment_text: None,
-            hint_before_oneline_comment_text: None,
-            hint_table_constructor: None,
-
-            remove_comments: None,
-            remove_single_newlines: None,
-            remove_all_newlines: None,
-            remove_spaces_between_tokens: None,
-            write_newline_at_eof: None,
-            write_newline_at_multiline_table: None,
-            write_newline_at_explist_multiline_table: None,
-
-            // indentation
-            indentation_string: None,
-            indent_var_suffix: None,
-            indent_one_line_var_suffix: None,
-            indent_exp_list: None,
-            indent_one_line_exp_list: None,
-
-            // format
-            newline_format_first_oneline_comment: None,
-            newline_format_first_multiline_comment: None,
-            newline_format_oneline_comment: None,
-            newline_format_multiline_comment: None,
-            newline_format_statement: None,
-            newline_format_do_end: None,
-            newline_format_for: None,
-            newline_format_function: None,
-            newline_format_if: None,
-            newline_format_repeat_until: None,
-            newline_format_table_constructor: None,
-            newline_format_table_field: None,
-            newline_format_while: None,
-            newline_format_binary_op: None,
-            newline_format_var_suffix: None,
-            newline_format_exp_list: None,
-            newline_format_exp_list_first: None,
-
-            // other
-            field_separator: None,
-            write_trailing_field_separator: None,
-            convert_charstring_to_normalstring: None,
-
-            // oneline
-            max_width: None,
-            force_single_line_binary_op: None,
-            force_single_line_table: None,
-            force_single_line_iv_table_field: None,
-            force_single_line_kv_table_field: None,
-            force_single_line_if: None,
-            force_single_line_top_level_function: None,
-            force_single_line_scoped_function: None,
-            force_single_line_var_suffix: None,
-            force_single_line_exp_list: None,
+    field_separator: String, Other, Domain::Strings(&[",", ";"]), "Separator of the table fields";
+    write_trailing_field_separator: bool, Other, Domain::Any,
+        "Write the separator after the last field of a multiline table constructor";
+    convert_charstring_to_normalstring: bool, Other, Domain::Any,
+        "Convert the 'single quoted' strings to \"double quoted\" ones";
+
+    max_width: usize, SingleLine, Domain::Positive,
+        "Maximum width of a line, the longer constructs are split over several lines";
+    force_single_line_binary_op: bool, SingleLine, Domain::Any,
+        "Keep a binary operation on a single line if it fits in `max_width`";
+    force_single_line_table: bool, SingleLine, Domain::Any,
+        "Keep a table constructor on a single line if it fits in `max_width`";
+    force_single_line_iv_table_field: bool, SingleLine, Domain::Any,
+        "Keep the sequential table fields on a single line if they fit in `max_width`";
+    force_single_line_kv_table_field: bool, SingleLine, Domain::Any,
+        "Keep the named table fields on a single line if they fit in `max_width`";
+    force_single_line_if: bool, SingleLine, Domain::Any,
+        "Keep an `if` statement on a single line if it fits in `max_width`";
+    force_single_line_top_level_function: bool, SingleLine, Domain::Any,
+        "Keep a top-level function on a single line if it fits in `max_width`";
+    force_single_line_scoped_function: bool, SingleLine, Domain::Any,
+        "Keep a nested function on a single line if it fits in `max_width`";
+    force_single_line_var_suffix: bool, SingleLine, Domain::Any,
+        "Keep the suffixes of a variable on a single line if they fit in `max_width`";
+    force_single_line_exp_list: bool, SingleLine, Domain::Any,
+        "Keep an expression list on a single line if it fits in `max_width`";
+}
+
+// Declares the options of the configuration which are not format options, every option is a line of
+// `name: type, domain, description`. The fields of `Config`, their default, merging, setting, printing and the
+// registry are generated from the declarations, the overrides and the format options are added to them.
+macro_rules! config_options {
+    ($($name:ident: $type:ty, $domain:expr, $description:literal;)+) => {
+        #[derive(Debug, PartialEq, Clone)]
+        pub struct Config {
+            $( pub $name: Option<$type>, )+
+            // the options of the files matching the globs, they are resolved by `for_file`
+            pub overrides: Vec<ConfigOverride>,
+            pub fmt: FormatOpts,
         }
-    }
+
+        impl Config {
+            pub const fn default() -> Self {
+                Config { $( $name: None, )+ overrides: Vec::new(), fmt: FormatOpts::default() }
+            }
+
+            /// Returns the values of the options which are set, the overrides are skipped
+            pub fn option_values(&self) -> Vec<(&'static str, OptionValue)> {
+                let mut values = self.fmt.option_values();
+                $( if let Some(value) = &self.$name {
+                    values.push((stringify!($name), value.to_option_value()));
+                } )+
+                values
+            }
+
+            /// Overrides the options which are set in `other`
+            pub fn merge(&mut self, other: &Config) {
+                $( if other.$name.is_some() {
+                    self.$name = other.$name.clone();
+                } )+
+                self.overrides.extend(other.overrides.iter().cloned());
+                self.fmt.merge(&other.fmt);
+            }
+
+            // returns `None` if there is no such option, the format options are set too
+            fn try_set_option(&mut self, option_name: &str, value: &OptionValue) -> Option<Result<(), String>> {
+                $( if option_name == stringify!($name) {
+                    return Some(set_option(&mut self.$name, option_name, value, &$domain));
+                } )+
+                self.fmt.try_set(option_name, value)
+            }
+        }
+
+        /// The registry of the options which are not format options
+        pub static CONFIG_OPTIONS: &[OptionInfo] = &[
+            $( OptionInfo {
+                name: stringify!($name),
+                type_name: <$type as OptionType>::TYPE_NAME,
+                category: Category::General,
+                domain: $domain,
+                description: $description,
+            }, )+
+            OptionInfo {
+                name: "overrides",
+                type_name: "array",
+                category: Category::General,
+                domain: Domain::Any,
+                description: "Option sections for the files matching their `files` globs, only in configuration files",
+            },
+        ];
+    };
+}
+
+config_options! {
+    line_range: (usize, usize), Domain::Any, "Format only the top-level statements of the lines `FIRST:LAST`";
+    error_recovery: bool, Domain::Any, "Keep the statements with syntax errors as is and format the rest of the file";
+    verify: bool, Domain::Any,
+        "Check that the output has the same syntax tree and does not change when formatted again";
+    root: bool, Domain::Any, "Stop the search of the configuration files of the parent directories";
+    style: String, STYLE_DOMAIN, "Built-in style which provides the format options which are not set";
+    lua_version: String, LUA_VERSION_DOMAIN,
+        "Dialect of the sources, by default `.luau` files are Luau and the scripts take the dialect of their shebang";
+}
+
+/// Returns the registry of all options
+pub fn all_options() -> impl Iterator<Item = &'static OptionInfo> {
+    CONFIG_OPTIONS.iter().chain(FORMAT_OPTIONS.iter())
 }
 
 impl Config {
-    pub const fn default() -> Self {
-        Config { line_range: None, fmt: FormatOpts::default() }
+    pub fn has_empty_format(&self) -> bool {
+        self.fmt == FormatOpts::default() && self.style.is_none()
     }
 
-    pub fn has_empty_format(&self) -> bool {
-        self.fmt == FormatOpts::default()
+    /// Returns the configuration with the format options of the `style` filled in
+    pub fn with_style(&self) -> Config {
+        match self.style.as_ref().and_then(|name| style::preset(name)) {
+            Some(mut fmt) => {
+                fmt.merge(&self.fmt);
+                Config { fmt, ..self.clone() }
+            }
+            None => self.clone(),
+        }
     }
 
+    /// Returns the dialect of the `lua_version` option
+    pub fn dialect(&self) -> parser::Dialect {
+        self.lua_version.as_deref().and_then(parser::Dialect::from_lua_version).unwrap_or(parser::Dialect::Lua54)
+    }
+
+    /// Returns the overrides matching the file in the order they are applied
+    pub fn matching_overrides(&self, file_path: &Path) -> Vec<&ConfigOverride> {
+        // the file may not exist yet
+        fn absolute_path(path: &Path) -> PathBuf {
+            fs::canonicalize(path)
+                .or_else(|_| std::env::current_dir().map(|dir| dir.join(path)))
+                .unwrap_or_else(|_| path.to_path_buf())
+        }
+
+        let file_path = absolute_path(file_path);
+        self.overrides
+            .iter()
+            .filter(|over| match file_util::rel_path_str(&file_path, &absolute_path(&over.base)) {
+                Some(rel_path) => over.files.iter().any(|glob| glob.matches_file(&rel_path)),
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Returns the configuration with the overrides matching the file applied
+    pub fn for_file(&self, file_path: &Path) -> Config {
+        let mut cfg = Config { overrides: Vec::new(), ..self.clone() };
+        for over in self.matching_overrides(file_path) {
+            cfg.merge(&over.config);
+        }
+        cfg
+    }
+
+    /// Sets the option from the command line, the errors are printed
     pub fn set(&mut self, option_name: &str, value_str: &str) {
-        macro_rules! set_param_value_as {
-            ($field:expr, $type:ty) => {
-                match value_str.parse::<$type>() {
-                    Ok(value) => $field = Some(value),
-                    _ => eprintln!("Invalid `{}` option value `{}`", option_name, value_str),
-                }
-            };
+        if let Err(err) = self.try_set(option_name, &OptionValue::Text(value_str.to_string())) {
+            eprintln!("{}", err);
         }
+    }
 
-        let re_lines_opt = Regex::new(r"^([0-9]+):([0-9]+)$").unwrap();
+    /// Sets the option if the value has the type of the option
+    pub fn try_set(&mut self, option_name: &str, value: &OptionValue) -> Result<(), String> {
+        if let Some(result) = self.try_set_option(option_name, value) {
+            return result;
+        }
 
         match option_name {
-            // hint
-            "replace_zero_spaces_with_hint" => set_param_value_as!(self.fmt.replace_zero_spaces_with_hint, bool),
-            "hint_after_multiline_comment" => set_param_value_as!(self.fmt.hint_after_multiline_comment, String),
-            "hint_after_multiline_comment_text" => {
-                set_param_value_as!(self.fmt.hint_after_multiline_comment_text, String)
+            "overrides" => Err("The `overrides` option is supported only in configuration files".to_string()),
+            _ => match similar_option_name(option_name) {
+                Some(name) => Err(format!("Invalid option name `{}`, did you mean `{}`?", option_name, name)),
+                None => Err(format!("Invalid option name `{}`", option_name)),
+            },
+        }
+    }
+
+    /// Reads the options of the `.lua`, `.toml` or `.json` configuration file over the options of `self`. All the
+    /// errors of the file are returned, the options are not applied partially.
+    pub fn reload_format_from_file(&self, file_path: &PathBuf) -> Result<Self, String> {
+        let file_name = file_path.display().to_string();
+        let content = match fs::read_to_string(file_path) {
+            Ok(content) => content,
+            Err(err) => return Err(format!("An error occured while reading config file `{}`: {}", file_name, err)),
+        };
+
+        let items = match file_path.extension().and_then(OsStr::to_str) {
+            Some("toml") => toml_config_items(&content),
+            Some("json") => json_config_items(&content),
+            _ => lua_config_items(&content, &file_name),
+        };
+        let items = items.map_err(|err| format!("{}: {}", file_name, err))?;
+
+        let mut cfg = self.clone();
+        let mut errors = Vec::new();
+        let base = file_path.parent().map(Path::to_path_buf).unwrap_or_default();
+        cfg.apply_config_items(items, &base, 0, &mut errors);
+
+        match errors.is_empty() {
+            true => Ok(cfg),
+            false => {
+                let errors: Vec<_> =
+                    errors.into_iter().map(|(line, err)| format!("{}:{}: {}", file_name, line, err)).collect();
+                Err(errors.join("\n"))
             }
-            "hint_before_comment" => set_param_value_as!(self.fmt.hint_before_comment, String),
-            "hint_before_multiline_comment_text" => {
-                set_param_value_as!(self.fmt.hint_before_multiline_comment_text, String)
+        }
+    }
+
+    // `line` is the line of the override section, it is 0 for the whole file
+    fn apply_config_items(&mut self, items: ConfigItems, base: &Path, line: usize, errors: &mut ConfigErrors) {
+        for (item_line, item) in items {
+            match item {
+                Ok((name, value)) if name == "overrides" && line == 0 => {
+                    self.overrides.extend(parse_overrides(value, base, item_line, errors))
+                }
+                Ok((name, _)) if line != 0 && (name == "overrides" || name == "root") => {
+                    errors.push((item_line, format!("The `{}` option is not supported in the override section", name)))
+                }
+                Ok((name, value)) => {
+                    if let Err(err) = self.try_set(&name, &value) {
+                        errors.push((item_line, err));
+                    }
+                }
+                Err(err) => errors.push((item_line, err)),
             }
-            "hint_before_oneline_comment_text" => {
-                set_param_value_as!(self.fmt.hint_before_oneline_comment_text, String)
+        }
+    }
+}
+
+fn parse_overrides(value: OptionValue, base: &Path, line: usize, errors: &mut ConfigErrors) -> Vec<ConfigOverride> {
+    let type_error = |name: &str, expected: &str, value: &OptionValue| {
+        format!("Invalid {} type, expected {}, found {}", name, expected, value.type_name())
+    };
+
+    let sections = match value {
+        OptionValue::Array(sections) => sections,
+        value => {
+            errors.push((line, type_error("`overrides` option", "array", &value)));
+            return Vec::new();
+        }
+    };
+
+    let mut overrides = Vec::new();
+    for section in sections {
+        let items = match section {
+            OptionValue::Table(items) => items,
+            value => {
+                errors.push((line, type_error("override section", "table", &value)));
+                continue;
             }
-            "hint_table_constructor" => set_param_value_as!(self.fmt.hint_table_constructor, String),
-
-            "remove_comments" => set_param_value_as!(self.fmt.remove_comments, bool),
-            "remove_single_newlines" => set_param_value_as!(self.fmt.remove_single_newlines, bool),
-            "remove_all_newlines" => set_param_value_as!(self.fmt.remove_all_newlines, bool),
-            "remove_spaces_between_tokens" => set_param_value_as!(self.fmt.remove_spaces_between_tokens, bool),
-            "write_newline_at_eof" => set_param_value_as!(self.fmt.write_newline_at_eof, bool),
-            "write_newline_at_multiline_table" => set_param_value_as!(self.fmt.write_newline_at_multiline_table, bool),
-            "write_newline_at_explist_multiline_table" => set_param_value_as!(self.fmt.write_newline_at_explist_multiline_table, bool),
-
-            // indentation
-            "indentation_string" => set_param_value_as!(self.fmt.indentation_string, String),
-            "indent_var_suffix" => set_param_value_as!(self.fmt.indent_var_suffix, bool),
-            "indent_one_line_var_suffix" => set_param_value_as!(self.fmt.indent_one_line_var_suffix, bool),
-            "indent_exp_list" => set_param_value_as!(self.fmt.indent_exp_list, bool),
-            "indent_one_line_exp_list" => set_param_value_as!(self.fmt.indent_one_line_exp_list, bool),
-
-            // format
-            "newline_format_first_oneline_comment" => {
-                set_param_value_as!(self.fmt.newline_format_first_oneline_comment, usize)
+        };
+
+        // the line of the section is the line of its first option
+        let section_line = items.first().map(|(item_line, _)| *item_line).unwrap_or(line);
+        let mut files = Vec::new();
+        let mut options = Vec::new();
+        for (item_line, item) in items {
+            match item {
+                Ok((name, value)) if name == "files" => match value {
+                    OptionValue::String(pattern) => match Glob::new(&pattern) {
+                        Ok(glob) => files.push(glob),
+                        Err(err) => errors.push((item_line, err)),
+                    },
+                    OptionValue::Array(patterns) => {
+                        for pattern in patterns {
+                            match pattern {
+                                OptionValue::String(pattern) => match Glob::new(&pattern) {
+                                    Ok(glob) => files.push(glob),
+                                    Err(err) => errors.push((item_line, err)),
+                                },
+                                value => errors.push((item_line, type_error("`files` item", "string", &value))),
+                            }
+                        }
+                    }
+                    value => errors.push((item_line, type_error("`files` option", "string or array", &value))),
+                },
+                item => options.push((item_line, item)),
             }
-            "newline_format_first_multiline_comment" => {
-                set_param_value_as!(self.fmt.newline_format_first_multiline_comment, usize)
+        }
+
+        let mut config = Config::default();
+        config.apply_config_items(options, base, section_line, errors);
+        match files.is_empty() {
+            true => errors.push((section_line, "The override section has no `files` option".to_string())),
+            false => overrides.push(ConfigOverride { files, base: base.to_path_buf(), config }),
+        }
+    }
+    overrides
+}
+
+/// Value of an option, the command line values are parsed according to the type of the option
+#[derive(Debug, PartialEq, Clone)]
+pub enum OptionValue {
+    Text(String),
+    Bool(bool),
+    Integer(i64),
+    String(String),
+    Array(Vec<OptionValue>),
+    Table(ConfigItems),
+    // the value of an unsupported type with the name of the type
+    Other(&'static str),
+}
+
+impl OptionValue {
+    fn type_name(&self) -> &'static str {
+        match self {
+            OptionValue::Text(_) => "text",
+            OptionValue::Bool(_) => "boolean",
+            OptionValue::Integer(_) => "integer",
+            OptionValue::String(_) => "string",
+            OptionValue::Array(_) => "array",
+            OptionValue::Table(_) => "table",
+            OptionValue::Other(type_name) => type_name,
+        }
+    }
+
+    fn to_lua(&self) -> String {
+        match self {
+            OptionValue::Bool(value) => value.to_string(),
+            OptionValue::Integer(value) => value.to_string(),
+            OptionValue::String(value) => lua_escape(value),
+            _ => "nil".to_string(),
+        }
+    }
+}
+
+// the Lua string literal of the text, `lua_unescape` restores the text
+fn lua_escape(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            // unlike `\ddd`, the braces keep the following digits out of the escape
+            ch if ch.is_control() => literal.push_str(&format!("\\u{{{:X}}}", ch as u32)),
+            ch => literal.push(ch),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+// processes the escape sequences of the text of a Lua string literal, the unknown escapes are kept as is. The byte
+// escapes make UTF-8 sequences together, e.g. `\226\128\148`, so the bytes are decoded once at the end.
+fn lua_unescape(text: &str) -> Result<String, String> {
+    let mut result = Vec::new();
+    let push_char = |result: &mut Vec<u8>, ch: char| result.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            push_char(&mut result, ch);
+            continue;
+        }
+
+        let escaped = match chars.next() {
+            Some('a') => Some('\x07'),
+            Some('b') => Some('\x08'),
+            Some('f') => Some('\x0C'),
+            Some('n') | Some('\n') => Some('\n'),
+            Some('r') => Some('\r'),
+            Some('t') => Some('\t'),
+            Some('v') => Some('\x0B'),
+            Some(ch @ ('\\' | '"' | '\'')) => Some(ch),
+            Some('z') => {
+                while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+                continue;
+            }
+            Some('x') => {
+                let digits: String = (0..2).filter_map(|_| chars.next_if(char::is_ascii_hexdigit)).collect();
+                result.extend(u8::from_str_radix(&digits, 16).ok());
+                continue;
             }
-            "newline_format_oneline_comment" => set_param_value_as!(self.fmt.newline_format_oneline_comment, usize),
-            "newline_format_multiline_comment" => set_param_value_as!(self.fmt.newline_format_multiline_comment, usize),
-            "newline_format_statement" => set_param_value_as!(self.fmt.newline_format_statement, usize),
-            "newline_format_do_end" => set_param_value_as!(self.fmt.newline_format_do_end, usize),
-            "newline_format_for" => set_param_value_as!(self.fmt.newline_format_for, usize),
-            "newline_format_function" => set_param_value_as!(self.fmt.newline_format_function, usize),
-            "newline_format_if" => set_param_value_as!(self.fmt.newline_format_if, usize),
-            "newline_format_repeat_until" => set_param_value_as!(self.fmt.newline_format_repeat_until, usize),
-            "newline_format_table_constructor" => set_param_value_as!(self.fmt.newline_format_table_constructor, usize),
-            "newline_format_table_field" => set_param_value_as!(self.fmt.newline_format_table_field, usize),
-            "newline_format_while" => set_param_value_as!(self.fmt.newline_format_while, usize),
-            "newline_format_binary_op" => set_param_value_as!(self.fmt.newline_format_binary_op, usize),
-            "newline_format_var_suffix" => set_param_value_as!(self.fmt.newline_format_var_suffix, usize),
-            "newline_format_exp_list" => set_param_value_as!(self.fmt.newline_format_exp_list, usize),
-            "newline_format_exp_list_first" => set_param_value_as!(self.fmt.newline_format_exp_list_first, usize),
-
-            // other
-            "field_separator" => set_param_value_as!(self.fmt.field_separator, String),
-            "write_trailing_field_separator" => set_param_value_as!(self.fmt.write_trailing_field_separator, bool),
-            "convert_charstring_to_normalstring" => {
-                set_param_value_as!(self.fmt.convert_charstring_to_normalstring, bool)
+            Some('u') if chars.next_if_eq(&'{').is_some() => {
+                let digits: String = std::iter::from_fn(|| chars.next_if(char::is_ascii_hexdigit)).collect();
+                chars.next_if_eq(&'}');
+                u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32)
             }
+            Some(ch) if ch.is_ascii_digit() => {
+                let mut digits = ch.to_string();
+                digits.extend((0..2).filter_map(|_| chars.next_if(char::is_ascii_digit)));
+                result.extend(digits.parse::<u8>().ok());
+                continue;
+            }
+            Some(ch) => {
+                result.push(b'\\');
+                Some(ch)
+            }
+            None => Some('\\'),
+        };
+        if let Some(ch) = escaped {
+            push_char(&mut result, ch);
+        }
+    }
+    String::from_utf8(result).map_err(|_| "Invalid string, its escape sequences are not valid UTF-8".to_string())
+}
+
+trait OptionType: Sized {
+    const TYPE_NAME: &'static str;
+
+    // parses the value of the command line
+    fn from_text(text: &str) -> Option<Self>;
 
-            // oneline
-            "max_width" => set_param_value_as!(self.fmt.max_width, usize),
-            "force_single_line_binary_op" => set_param_value_as!(self.fmt.force_single_line_binary_op, bool),
-            "force_single_line_table" => set_param_value_as!(self.fmt.force_single_line_table, bool),
-            "force_single_line_iv_table_field" => set_param_value_as!(self.fmt.force_single_line_iv_table_field, bool),
-            "force_single_line_kv_table_field" => set_param_value_as!(self.fmt.force_single_line_kv_table_field, bool),
-            "force_single_line_if" => set_param_value_as!(self.fmt.force_single_line_if, bool),
-            "force_single_line_top_level_function" => {
-                set_param_value_as!(self.fmt.force_single_line_top_level_function, bool)
+    fn from_typed_value(value: &OptionValue) -> Option<Self>;
+
+    fn to_option_value(&self) -> OptionValue;
+
+    fn from_option_value(option_name: &str, value: &OptionValue) -> Result<Self, String> {
+        match value {
+            OptionValue::Text(text) => {
+                Self::from_text(text).ok_or_else(|| format!("Invalid `{}` option value `{}`", option_name, text))
             }
-            "force_single_line_scoped_function" => set_param_value_as!(self.fmt.force_single_line_scoped_function, bool),
-            "force_single_line_var_suffix" => set_param_value_as!(self.fmt.force_single_line_var_suffix, bool),
-            "force_single_line_exp_list" => set_param_value_as!(self.fmt.force_single_line_exp_list, bool),
-
-            "line_range" => match re_lines_opt.captures_iter(value_str).next() {
-                Some(cap) => match (cap[1].parse(), cap[2].parse()) {
-                    (Ok(l1), Ok(l2)) => self.line_range = Some((l1, l2)),
-                    _ => eprintln!("Invalid `{}` option value `{}`", option_name, value_str),
-                },
-                _ => eprintln!("Invalid `{}` option value `{}`", option_name, value_str),
-            },
+            _ => Self::from_typed_value(value).ok_or_else(|| {
+                format!(
+                    "Invalid `{}` option type, expected {}, found {}",
+                    option_name,
+                    Self::TYPE_NAME,
+                    value.type_name()
+                )
+            }),
+        }
+    }
+}
+
+impl OptionType for bool {
+    const TYPE_NAME: &'static str = "boolean";
 
-            _ => eprintln!("Invalid option name `{}`", option_name),
+    fn from_text(text: &str) -> Option<Self> {
+        text.parse().ok()
+    }
+
+    fn from_typed_value(value: &OptionValue) -> Option<Self> {
+        match value {
+            OptionValue::Bool(value) => Some(*value),
+            _ => None,
         }
     }
 
-    fn load_options_from_config(&mut self, node: &parser::lua_ast::Node) {
-        use parser::lua_ast::Node::*;
-        match node {
-            VarsExprs(_, _, varlist, exprlist) => match (&**varlist, &**exprlist) {
-                (VarList(_, vars), ExpList(_, exprs)) => match (vars.iter().next(), exprs.iter().next()) {
-                    (Some((_, var, _, _)), Some((_, expr, _, _))) => match (var, expr) {
-                        (Name(_, str_name), Numeral(_, str_expr))
-                        | (Name(_, str_name), NormalStringLiteral(_, str_expr))
-                        | (Name(_, str_name), CharStringLiteral(_, str_expr)) => self.set(str_name, str_expr),
-
-                        (Name(_, str_name), True(..)) => self.set(str_name, "true"),
-                        (Name(_, str_name), False(..)) => self.set(str_name, "false"),
-                        _ => {}
-                    },
-                    _ => {}
-                },
-                _ => {}
+    fn to_option_value(&self) -> OptionValue {
+        OptionValue::Bool(*self)
+    }
+}
+
+impl OptionType for usize {
+    const TYPE_NAME: &'static str = "non-negative integer";
+
+    fn from_text(text: &str) -> Option<Self> {
+        text.parse().ok()
+    }
+
+    fn from_typed_value(value: &OptionValue) -> Option<Self> {
+        match value {
+            OptionValue::Integer(value) if *value >= 0 => Some(*value as usize),
+            _ => None,
+        }
+    }
+
+    fn to_option_value(&self) -> OptionValue {
+        OptionValue::Integer(*self as i64)
+    }
+}
+
+impl OptionType for String {
+    const TYPE_NAME: &'static str = "string";
+
+    fn from_text(text: &str) -> Option<Self> {
+        text.parse().ok()
+    }
+
+    fn from_typed_value(value: &OptionValue) -> Option<Self> {
+        match value {
+            OptionValue::String(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn to_option_value(&self) -> OptionValue {
+        OptionValue::String(self.clone())
+    }
+}
+
+// the first and the last line, `FIRST:LAST`
+impl OptionType for (usize, usize) {
+    const TYPE_NAME: &'static str = "string";
+
+    fn from_text(text: &str) -> Option<Self> {
+        let re_lines_opt = Regex::new(r"^([0-9]+):([0-9]+)$").unwrap();
+        let cap = re_lines_opt.captures(text)?;
+        Some((cap[1].parse().ok()?, cap[2].parse().ok()?))
+    }
+
+    fn from_typed_value(value: &OptionValue) -> Option<Self> {
+        match value {
+            OptionValue::String(value) => Self::from_text(value),
+            _ => None,
+        }
+    }
+
+    fn to_option_value(&self) -> OptionValue {
+        OptionValue::String(format!("{}:{}", self.0, self.1))
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for j in 0..b.len() {
+            let cur = row[j + 1];
+            row[j + 1] = (prev + (ca != b[j]) as usize).min(row[j] + 1).min(cur + 1);
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+fn similar_option_name(option_name: &str) -> Option<&'static str> {
+    let max_distance = std::cmp::max(2, option_name.len() / 4);
+    all_options()
+        .map(|option| (edit_distance(option_name, option.name), option.name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, name)| name)
+}
+
+// the errors of the configuration file with the numbers of their lines
+type ConfigErrors = Vec<(usize, String)>;
+
+/// The options of the configuration file with the numbers of their lines
+pub type ConfigItems = Vec<(usize, Result<(String, OptionValue), String>)>;
+
+fn toml_config_items(content: &str) -> Result<ConfigItems, String> {
+    use crate::toml::{Toml, TomlPairs};
+
+    fn toml_value(value: Toml) -> OptionValue {
+        match value {
+            Toml::Bool(value) => OptionValue::Bool(value),
+            Toml::Integer(value) => OptionValue::Integer(value),
+            Toml::String(value) => OptionValue::String(value),
+            Toml::Array(values) => OptionValue::Array(values.into_iter().map(toml_value).collect()),
+            Toml::Table(pairs) => OptionValue::Table(toml_items(pairs)),
+            value => OptionValue::Other(value.type_name()),
+        }
+    }
+
+    fn toml_items(pairs: TomlPairs) -> ConfigItems {
+        pairs.into_iter().map(|(name, value, line)| (line, Ok((name, toml_value(value))))).collect()
+    }
+
+    Ok(toml_items(crate::toml::parse(content)?))
+}
+
+fn json_config_items(content: &str) -> Result<ConfigItems, String> {
+    use crate::json::Json;
+
+    // the members of the nested objects have the line of the top-level member
+    fn json_value(value: Json, line: usize) -> OptionValue {
+        match value {
+            Json::Bool(value) => OptionValue::Bool(value),
+            Json::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => OptionValue::Integer(value as i64),
+            Json::Number(_) => OptionValue::Other("number"),
+            Json::String(value) => OptionValue::String(value),
+            Json::Null => OptionValue::Other("null"),
+            Json::Array(values) => {
+                OptionValue::Array(values.into_iter().map(|value| json_value(value, line)).collect())
+            }
+            Json::Object(members) => OptionValue::Table(
+                members.into_iter().map(|(name, value)| (line, Ok((name, json_value(value, line))))).collect(),
+            ),
+        }
+    }
+
+    let members = Json::parse_object_members(content)?;
+    Ok(members.into_iter().map(|(name, value, line)| (line, Ok((name, json_value(value, line))))).collect())
+}
+
+fn lua_config_items(content: &str, file_name: &str) -> Result<ConfigItems, String> {
+    use parser::lua_ast::Node::{self, *};
+
+    fn line_of(content: &str, pos: usize) -> usize {
+        content.chars().take(pos).filter(|&ch| ch == '\n').count() + 1
+    }
+
+    // the sequential fields make an array, the named ones make a table
+    fn lua_value(node: &Node, content: &str) -> Result<OptionValue, String> {
+        let value = match node {
+            True(_) => OptionValue::Bool(true),
+            False(_) => OptionValue::Bool(false),
+            Numeral(_, s) => match s.parse() {
+                Ok(value) => OptionValue::Integer(value),
+                _ => OptionValue::Other("number"),
+            },
+            NormalStringLiteral(_, s) | CharStringLiteral(_, s) => OptionValue::String(lua_unescape(s)?),
+            TableConstructorEmpty(..) => OptionValue::Array(Vec::new()),
+            TableConstructor(_, _, fields, _) => match &**fields {
+                Fields(_, fields, _) if fields.iter().all(|(_, field, _, _)| matches!(field, FieldSequential(..))) => {
+                    let values = fields.iter().map(|(_, field, _, _)| match field {
+                        FieldSequential(_, value) => lua_value(value, content),
+                        _ => unreachable!(),
+                    });
+                    OptionValue::Array(values.collect::<Result<_, _>>()?)
+                }
+                Fields(_, fields, _) => {
+                    let items = fields.iter().map(|(_, field, _, _)| match field {
+                        FieldNamed(loc, _, name, value) => match &**name {
+                            Name(_, name) => {
+                                (line_of(content, loc.0), lua_value(value, content).map(|value| (name.clone(), value)))
+                            }
+                            _ => (line_of(content, loc.0), Err("Invalid field, expected `name = value`".to_string())),
+                        },
+                        FieldNamedBracket(loc, ..) | FieldSequential(loc, _) => {
+                            (line_of(content, loc.0), Err("Invalid field, expected `name = value`".to_string()))
+                        }
+                        _ => (0, Err("Invalid field, expected `name = value`".to_string())),
+                    });
+                    OptionValue::Table(items.collect())
+                }
+                _ => OptionValue::Other("table"),
             },
+            _ => OptionValue::Other("expression"),
+        };
+        Ok(value)
+    }
+
+    fn collect_items(node: &Node, content: &str, start: usize, items: &mut ConfigItems) {
+        match node {
             StatementList(_, stts) => {
-                for (_, node) in stts {
-                    self.load_options_from_config(node);
+                for (gap, node) in stts {
+                    collect_items(node, content, gap.1, items);
                 }
             }
-            _ => {}
+            VarsExprs(_, _, varlist, exprlist) => {
+                let item = match (&**varlist, &**exprlist) {
+                    (VarList(_, vars), ExpList(_, exprs)) if vars.len() == 1 && exprs.len() == 1 => {
+                        match (&vars[0].1, &exprs[0].1) {
+                            (Name(_, name), expr) => lua_value(expr, content).map(|value| (name.clone(), value)),
+                            _ => Err("Invalid option name, expected `name = value`".to_string()),
+                        }
+                    }
+                    _ => Err("Invalid statement, expected a single `name = value` assignment".to_string()),
+                };
+                items.push((line_of(content, start), item));
+            }
+            _ => items.push((line_of(content, start), Err("Invalid statement, expected `name = value`".to_string()))),
         }
     }
 
-    pub fn reload_format_from_file(&self, file_path: &PathBuf) -> Result<Self, String> {
-        let content = fs::read_to_string(file_path)
-            .expect(&format!("An error occured while reading config file `{}`", file_path.display()));
+    let node_tree = match parser::parse_lua(content) {
+        Ok(node_tree) => node_tree,
+        Err(err) => {
+            let err = parser::SyntaxError::from_parse_error(content, err);
+            return Err(err.with_file_name(file_name).to_string());
+        }
+    };
 
-        let mut cfg = self.clone();
+    let mut items = Vec::new();
+    match &node_tree {
+        Chunk(_, node, _) | SheBangChunk(_, _, _, node, _) => collect_items(node, content, 0, &mut items),
+        _ => {}
+    }
 
-        use parser::lua_ast::Node::*;
-        match parser::parse_lua(&content) {
-            Ok(node_tree) => {
-                match node_tree {
-                    Chunk(_, node, _) => cfg.load_options_from_config(&node),
-                    SheBangChunk(_, _, _, node, _) => cfg.load_options_from_config(&node),
-                    _ => {}
-                };
+    Ok(items)
+}
+
+/// Returns the content of a `.luafmt.lua` file with the options of the merged layers, every option is commented with
+/// the name of the last layer which sets it
+pub fn lua_config_with_sources(layers: &[(String, Config)]) -> String {
+    let mut merged = Config::default();
+    let mut sources = HashMap::new();
+    for (source, layer) in layers {
+        merged.merge(layer);
+        for (name, _) in layer.option_values() {
+            sources.insert(name, source.as_str());
+        }
+    }
+
+    let mut content = String::new();
+    for (name, value) in merged.option_values() {
+        content.push_str(&format!("{} = {} -- {}\n", name, value.to_lua(), sources[name]));
+    }
+    content
+}
+
+/// Returns the content of a `.luafmt.lua` file with all format options and their descriptions. The options are set to
+/// the values of the `default` style, the options which the style does not set are commented out.
+pub fn documented_config() -> String {
+    let style = Config { fmt: style::preset("default").unwrap_or_else(FormatOpts::default), ..Config::default() };
+    let defaults: HashMap<_, _> = style.option_values().into_iter().collect();
+
+    let mut content = String::new();
+    content.push_str("-- LuaFmt configuration, the options override the options of the configuration files of\n");
+    content.push_str("-- the parent directories unless `root = true` is set. The options of a built-in style\n");
+    content.push_str(&format!("-- are selected with `style = \"NAME\"`, one of: {}.\n", style::STYLES.join(", ")));
+
+    let mut category = None;
+    for option in FORMAT_OPTIONS.iter() {
+        if category != Some(option.category) {
+            content.push_str(&format!("\n-- {}\n", option.category.title()));
+            category = Some(option.category);
+        }
+
+        let values = option.domain.describe(option.type_name);
+        content.push_str(&format!("\n-- {}\n", option.description));
+        match defaults.get(option.name) {
+            Some(value) => {
+                let value = value.to_lua();
+                content.push_str(&format!("-- type: {}, values: {}, default: {}\n", option.type_name, values, value));
+                content.push_str(&format!("{} = {}\n", option.name, value));
             }
-            Err(err) => {
-                return Err(format!("An error occured while parsing config file `{}`: {}", file_path.display(), err))
+            None => {
+                let example = match (option.type_name, option.domain) {
+                    ("boolean", _) => "true".to_string(),
+                    (_, Domain::Integers(values)) => values[0].to_string(),
+                    (_, Domain::Strings(values)) => format!("\"{}\"", values[0]),
+                    ("string", _) => "\" \"".to_string(),
+                    _ => "1".to_string(),
+                };
+                content.push_str(&format!("-- type: {}, values: {}, default: not set\n", option.type_name, values));
+                content.push_str(&format!("-- {} = {}\n", option.name, example));
             }
         }
-
-        Ok(cfg)
     }
+    content
 }
 
 impl fmt::Display for Config {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        macro_rules! print_opt {
-            ($field:expr, $name:literal) => {
-                if $field.is_some() {
-                    write!(f, "\t{}: {:?},\n", $name, $field.as_ref().unwrap())?;
-                }
-            };
-        }
-
-        write!(f, "{{\n")?;
-
-        // hint
-        print_opt!(self.fmt.replace_zero_spaces_with_hint, "replace_zero_spaces_with_hint");
-        print_opt!(self.fmt.hint_after_multiline_comment, "hint_after_multiline_comment");
-        print_opt!(self.fmt.hint_after_multiline_comment_text, "hint_after_multiline_comment_text");
-        print_opt!(self.fmt.hint_before_comment, "hint_before_comment");
-        print_opt!(self.fmt.hint_before_multiline_comment_text, "hint_before_multiline_comment_text");
-        print_opt!(self.fmt.hint_before_oneline_comment_text, "hint_before_oneline_comment_text");
-        print_opt!(self.fmt.hint_table_constructor, "hint_table_constructor");
-
-        print_opt!(self.fmt.remove_comments, "remove_comments");
-        print_opt!(self.fmt.remove_single_newlines, "remove_single_newlines");
-        print_opt!(self.fmt.remove_all_newlines, "remove_all_newlines");
-        print_opt!(self.fmt.remove_spaces_between_tokens, "remove_spaces_between_tokens");
-        print_opt!(self.fmt.write_newline_at_eof, "write_newline_at_eof");
-        print_opt!(self.fmt.write_newline_at_multiline_table, "write_newline_at_multiline_table");
-        print_opt!(self.fmt.write_newline_at_explist_multiline_table, "write_newline_at_explist_multiline_table");
-
-        // indentation
-        print_opt!(self.fmt.indentation_string, "indentation_string");
-        print_opt!(self.fmt.indent_var_suffix, "indent_var_suffix");
-        print_opt!(self.fmt.indent_one_line_var_suffix, "indent_one_line_var_suffix");
-        print_opt!(self.fmt.indent_exp_list, "indent_exp_list");
-        print_opt!(self.fmt.indent_one_line_exp_list, "indent_one_line_exp_list");
-
-        // format
-        print_opt!(self.fmt.newline_format_first_oneline_comment, "newline_format_first_oneline_comment");
-        print_opt!(self.fmt.newline_format_first_multiline_comment, "newline_format_first_multiline_comment");
-        print_opt!(self.fmt.newline_format_oneline_comment, "newline_format_oneline_comment");
-        print_opt!(self.fmt.newline_format_multiline_comment, "newline_format_multiline_comment");
-        print_opt!(self.fmt.newline_format_statement, "newline_format_statement");
-        print_opt!(self.fmt.newline_format_do_end, "newline_format_do_end");
-        print_opt!(self.fmt.newline_format_for, "newline_format_for");
-        print_opt!(self.fmt.newline_format_function, "newline_format_function");
-        print_opt!(self.fmt.newline_format_if, "newline_format_if");
-        print_opt!(self.fmt.newline_format_repeat_until, "newline_format_repeat_until");
-        print_opt!(self.fmt.newline_format_table_constructor, "newline_format_table_constructor");
-        print_opt!(self.fmt.newline_format_table_field, "newline_format_table_field");
-        print_opt!(self.fmt.newline_format_while, "newline_format_while");
-        print_opt!(self.fmt.newline_format_binary_op, "newline_format_binary_op");
-        print_opt!(self.fmt.newline_format_var_suffix, "newline_format_var_suffix");
-        print_opt!(self.fmt.newline_format_exp_list, "newline_format_exp_list");
-        print_opt!(self.fmt.newline_format_exp_list_first, "newline_format_exp_list_first");
-
-        // other
-        print_opt!(self.fmt.field_separator, "field_separator");
-        print_opt!(self.fmt.write_trailing_field_separator, "write_trailing_field_separator");
-        print_opt!(self.fmt.convert_charstring_to_normalstring, "convert_charstring_to_normalstring");
-
-        // oneline
-        print_opt!(self.fmt.max_width, "max_width");
-        print_opt!(self.fmt.force_single_line_binary_op, "force_single_line_binary_op");
-        print_opt!(self.fmt.force_single_line_table, "force_single_line_table");
-        print_opt!(self.fmt.force_single_line_iv_table_field, "force_single_line_iv_table_field");
-        print_opt!(self.fmt.force_single_line_kv_table_field, "force_single_line_kv_table_field");
-        print_opt!(self.fmt.force_single_line_if, "force_single_line_if");
-        print_opt!(self.fmt.force_single_line_top_level_function, "force_single_line_top_level_function");
-        print_opt!(self.fmt.force_single_line_scoped_function, "force_single_line_scoped_function");
-        print_opt!(self.fmt.force_single_line_var_suffix, "force_single_line_var_suffix");
-        print_opt!(self.fmt.force_single_line_exp_list, "force_single_line_exp_list");
-
-        print_opt!(self.line_range, "line_range");
+        writeln!(f, "{{")?;
+        for (name, value) in self.option_values() {
+            match value {
+                OptionValue::String(value) => writeln!(f, "\t{}: {:?},", name, value)?,
+                value => writeln!(f, "\t{}: {},", name, value.to_lua())?,
+            }
+        }
+        for over in &self.overrides {
+            let files: Vec<_> = over.files.iter().map(Glob::pattern).collect();
+            writeln!(f, "\toverride {:?}: {},", files, over.config.to_string().replace('\n', "\n\t"))?;
+        }
 
         write!(f, "}}")?;
         Ok(())
@@ -415,6 +1014,8 @@ pub struct State {
     pub comment_pos_range: Option<(usize, usize)>,
     pub comment_offset: usize,
     pub chars_to_bytes: HashMap::<usize, usize>,
+    pub disabled_ranges: Vec<(usize, usize)>,
+    pub ignore_next_positions: Vec<usize>,
 }
 
 impl State {
@@ -428,6 +1029,8 @@ impl State {
             comment_pos_range: None,
             comment_offset: 0,
             chars_to_bytes: HashMap::new(),
+            disabled_ranges: Vec::new(),
+            ignore_next_positions: Vec::new(),
         }
     }
 }
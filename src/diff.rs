@@ -0,0 +1,303 @@
+use std::fmt::Write;
+
+/// Edit of the sequence, the items are referred by their indexes in the old and new sequences
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+// splits text into lines keeping the line terminators
+fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+// the search of the middle snake gives up after this number of differences and the region is replaced as a whole, a
+// single hunk even if it has common items. So the time of the diff is O((N + M) * MAX_COST) instead of the
+// O((N + M) * D) of the shortest edit script with D differences, e.g. for the texts which have nothing in common.
+const MAX_COST: usize = 4096;
+
+/// Returns the edits which turn `old` items into `new` ones, the deletions precede the insertions. The shortest edit
+/// script is searched in linear space (Myers' algorithm) for the items which differ after skipping the common prefix
+/// and suffix.
+pub fn diff_items<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    diff_range(old, new, 0, 0, &mut edits);
+
+    // the deletions and insertions between the equal items are reordered
+    let mut result = Vec::with_capacity(edits.len());
+    let mut inserts = Vec::new();
+    for edit in edits {
+        match edit {
+            Edit::Insert(..) => inserts.push(edit),
+            Edit::Delete(..) => result.push(edit),
+            Edit::Equal(..) => {
+                result.append(&mut inserts);
+                result.push(edit);
+            }
+        }
+    }
+    result.append(&mut inserts);
+
+    result
+}
+
+// appends the edits of `old` and `new` which start at `old_start` and `new_start` of the whole sequences
+fn diff_range<T: PartialEq>(old: &[T], new: &[T], old_start: usize, new_start: usize, edits: &mut Vec<Edit>) {
+    let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+
+    edits.extend((0..prefix).map(|i| Edit::Equal(old_start + i, new_start + i)));
+
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    let (old_mid_start, new_mid_start) = (old_start + prefix, new_start + prefix);
+
+    match middle_snake(old_mid, new_mid) {
+        Some((x0, y0, x1, y1)) => {
+            diff_range(&old_mid[..x0], &new_mid[..y0], old_mid_start, new_mid_start, edits);
+            edits.extend((0..x1 - x0).map(|i| Edit::Equal(old_mid_start + x0 + i, new_mid_start + y0 + i)));
+            diff_range(&old_mid[x1..], &new_mid[y1..], old_mid_start + x1, new_mid_start + y1, edits);
+        }
+        None => {
+            edits.extend((0..old_mid.len()).map(|i| Edit::Delete(old_mid_start + i)));
+            edits.extend((0..new_mid.len()).map(|j| Edit::Insert(new_mid_start + j)));
+        }
+    }
+
+    let (old_suffix, new_suffix) = (old_start + old.len() - suffix, new_start + new.len() - suffix);
+    edits.extend((0..suffix).map(|k| Edit::Equal(old_suffix + k, new_suffix + k)));
+}
+
+// returns the start and the end of the middle snake of the shortest edit script, `None` if one of the sequences is
+// empty or the script costs more than `MAX_COST`
+fn middle_snake<T: PartialEq>(old: &[T], new: &[T]) -> Option<(usize, usize, usize, usize)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    if n == 0 || m == 0 {
+        return None;
+    }
+
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let limit = ((n + m + 1) / 2).min(MAX_COST as isize);
+
+    // the furthest reaching x on every diagonal k = x - y, backwards for the reversed sequences
+    let offset = limit + 1;
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = vec![0isize; 2 * offset as usize + 1];
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..=limit {
+        for k in (-d..=d).step_by(2) {
+            let mut x = match k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                true => forward[at(k + 1)],
+                false => forward[at(k - 1)] + 1,
+            };
+            let (x0, y0) = (x, x - k);
+            while x < n && x - k < m && old[x as usize] == new[(x - k) as usize] {
+                x += 1;
+            }
+            forward[at(k)] = x;
+
+            let c = delta - k;
+            if odd && c.abs() < d && x + backward[at(c)] >= n {
+                return Some((x0 as usize, y0 as usize, x as usize, (x - k) as usize));
+            }
+        }
+
+        for c in (-d..=d).step_by(2) {
+            let mut x = match c == -d || (c != d && backward[at(c - 1)] < backward[at(c + 1)]) {
+                true => backward[at(c + 1)],
+                false => backward[at(c - 1)] + 1,
+            };
+            let (x0, y0) = (x, x - c);
+            while x < n && x - c < m && old[(n - 1 - x) as usize] == new[(m - 1 - (x - c)) as usize] {
+                x += 1;
+            }
+            backward[at(c)] = x;
+
+            let k = delta - c;
+            if !odd && k.abs() <= d && x + forward[at(k)] >= n {
+                return Some(((n - x) as usize, (m - x + c) as usize, (n - x0) as usize, (m - y0) as usize));
+            }
+        }
+    }
+
+    None
+}
+
+fn write_line(out: &mut String, prefix: char, line: &str) {
+    out.push(prefix);
+    out.push_str(line);
+    if !line.ends_with('\n') {
+        out.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+// hunk ranges are 1-based, an empty range points to the line before it
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// Returns the unified diff between `old` and `new` texts with `context` lines around every change,
+/// or an empty string if the texts are equal.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str, context: usize) -> String {
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+    let edits = diff_items(&old_lines, &new_lines);
+
+    let changes: Vec<usize> =
+        edits.iter().enumerate().filter(|(_, e)| !matches!(e, Edit::Equal(..))).map(|(i, _)| i).collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // group changes which are close enough to share their context lines
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    for &pos in &changes {
+        match groups.last_mut() {
+            Some((_, end)) if pos <= *end + 2 * context + 1 => *end = pos,
+            _ => groups.push((pos, pos)),
+        }
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "--- {}", old_name);
+    let _ = writeln!(out, "+++ {}", new_name);
+
+    for (first, last) in groups {
+        let begin = first.saturating_sub(context);
+        let end = (last + context + 1).min(edits.len());
+        let hunk = &edits[begin..end];
+
+        // position of the hunk is defined by the lines which precede it
+        let old_start = edits[..begin].iter().filter(|e| !matches!(e, Edit::Insert(..))).count();
+        let new_start = edits[..begin].iter().filter(|e| !matches!(e, Edit::Delete(..))).count();
+        let old_len = hunk.iter().filter(|e| !matches!(e, Edit::Insert(..))).count();
+        let new_len = hunk.iter().filter(|e| !matches!(e, Edit::Delete(..))).count();
+
+        let _ = writeln!(out, "@@ -{} +{} @@", hunk_range(old_start, old_len), hunk_range(new_start, new_len));
+        for edit in hunk {
+            match *edit {
+                Edit::Equal(i, _) => write_line(&mut out, ' ', old_lines[i]),
+                Edit::Delete(i) => write_line(&mut out, '-', old_lines[i]),
+                Edit::Insert(j) => write_line(&mut out, '+', new_lines[j]),
+            }
+        }
+    }
+
+    out
+}
+
+#[test]
+fn test_unified_diff() {
+    assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new", 3), "");
+    assert_eq!(
+        unified_diff("a\nb\nc\n", "a\nB\nc\n", "old", "new", 3),
+        "--- old\n+++ new\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"
+    );
+    assert_eq!(unified_diff("", "a\n", "old", "new", 3), "--- old\n+++ new\n@@ -0,0 +1 @@\n+a\n");
+    assert_eq!(unified_diff("a\n", "", "old", "new", 3), "--- old\n+++ new\n@@ -1 +0,0 @@\n-a\n");
+    assert_eq!(
+        unified_diff("a\nb", "a\nb\n", "old", "new", 3),
+        "--- old\n+++ new\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n"
+    );
+
+    let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+    let new = "0\n2\n3\n4\n5\n6\n7\n8\nX\n";
+    assert_eq!(
+        unified_diff(old, new, "old", "new", 1),
+        "--- old\n+++ new\n@@ -1,2 +1,2 @@\n-1\n+0\n 2\n@@ -8,2 +8,2 @@\n 8\n-9\n+X\n"
+    );
+    assert_eq!(
+        unified_diff(old, new, "old", "new", 3),
+        "--- old\n+++ new\n@@ -1,4 +1,4 @@\n-1\n+0\n 2\n 3\n 4\n@@ -6,4 +6,4 @@\n 6\n 7\n 8\n-9\n+X\n"
+    );
+    assert_eq!(unified_diff(old, new, "old", "new", 4).matches("@@ -").count(), 1);
+
+    // the lines of large generated files are compared in linear memory
+    let old = (0..100_000).map(|i| format!("x{}=1\n", i)).collect::<String>();
+    let new =
+        (0..100_000).map(|i| format!("x{}{}\n", i, if i % 1000 == 0 { " = 1" } else { "=1" })).collect::<String>();
+    let diff = unified_diff(&old, &new, "old", "new", 0);
+    assert_eq!(diff.matches("@@ -").count(), 100);
+    assert!(diff.contains("@@ -1001 +1001 @@\n-x1000=1\n+x1000 = 1\n"));
+}
+
+#[cfg(test)]
+fn apply_edits<T: Clone>(old: &[T], new: &[T], edits: &[Edit]) -> Vec<T> {
+    edits
+        .iter()
+        .filter_map(|edit| match *edit {
+            Edit::Equal(i, _) => Some(old[i].clone()),
+            Edit::Insert(j) => Some(new[j].clone()),
+            Edit::Delete(_) => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_diff_items() {
+    let equal_count = |edits: &[Edit]| edits.iter().filter(|e| matches!(e, Edit::Equal(..))).count();
+
+    let (old, new) = (b"abcabba", b"cbabac");
+    let edits = diff_items(old, new);
+    assert_eq!(apply_edits(old, new, &edits), new);
+    assert_eq!(equal_count(&edits), 4);
+
+    let (old, new) = (b"xaxbxc", b"aybycy");
+    let edits = diff_items(old, new);
+    assert_eq!(apply_edits(old, new, &edits), new);
+    assert_eq!(equal_count(&edits), 3);
+    assert_eq!(&edits[..3], &[Edit::Delete(0), Edit::Equal(1, 0), Edit::Delete(2)]);
+
+    assert_eq!(diff_items::<u8>(b"", b"ab"), vec![Edit::Insert(0), Edit::Insert(1)]);
+    assert_eq!(diff_items::<u8>(b"ab", b""), vec![Edit::Delete(0), Edit::Delete(1)]);
+}
+
+#[test]
+fn test_diff_items_large() {
+    // every hundredth item of a large sequence is changed, the whole LCS table would not fit in memory
+    let old: Vec<u32> = (0..200_000).collect();
+    let new: Vec<u32> = old.iter().map(|&i| if i % 100 == 50 { i + 1_000_000 } else { i }).collect();
+    let edits = diff_items(&old, &new);
+    assert_eq!(apply_edits(&old, &new, &edits), new);
+    assert_eq!(edits.iter().filter(|e| matches!(e, Edit::Equal(..))).count(), 198_000);
+
+    // the sequences without common items are replaced as a whole
+    let new: Vec<u32> = (200_000..400_000).collect();
+    let edits = diff_items(&old, &new);
+    assert_eq!(apply_edits(&old, &new, &edits), new);
+    assert!(edits[..200_000].iter().all(|e| matches!(e, Edit::Delete(..))));
+}
+
+#[test]
+fn test_diff_items_over_max_cost() {
+    let equal_count = |edits: &[Edit]| edits.iter().filter(|e| matches!(e, Edit::Equal(..))).count();
+    let every_other_changed = |len: u32| -> (Vec<u32>, Vec<u32>) {
+        let old: Vec<u32> = (0..len).collect();
+        let new = old.iter().map(|&i| if i % 2 == 0 { i + len } else { i }).collect();
+        (old, new)
+    };
+
+    // the common items are found while the differences are cheaper than `MAX_COST`
+    let (old, new) = every_other_changed(MAX_COST as u32);
+    let edits = diff_items(&old, &new);
+    assert_eq!(apply_edits(&old, &new, &edits), new);
+    assert_eq!(equal_count(&edits), MAX_COST / 2);
+
+    // the more expensive region is replaced as a whole, only the common suffix is kept
+    let (old, new) = every_other_changed(3 * MAX_COST as u32);
+    let edits = diff_items(&old, &new);
+    assert_eq!(apply_edits(&old, &new, &edits), new);
+    assert_eq!(equal_count(&edits), 1);
+
+    let lines = |items: &[u32]| items.iter().map(|i| format!("{}\n", i)).collect::<String>();
+    let diff = unified_diff(&lines(&old), &lines(&new), "old", "new", 3);
+    assert_eq!(diff.lines().filter(|line| line.starts_with("@@")).count(), 1);
+}
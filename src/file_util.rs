@@ -1,34 +1,238 @@
 use std::ffi::OsStr;
 use std::fs;
-use std::io::{self, Error, ErrorKind};
+use std::io::{self, BufRead, Error, ErrorKind, Read};
+use std::iter;
 use std::path::{Path, PathBuf};
 
-pub fn get_path_files(path: &PathBuf, recursive: bool, ext: &str, filter_prefix: &str) -> io::Result<Vec<PathBuf>> {
-    let mut paths = Vec::new();
+use crate::glob::Glob;
 
-    if path.is_dir() {
-        for entry in fs::read_dir(path)? {
+// the files with gitignore-style patterns of the excluded paths
+pub static IGNORE_FILES: [&str; 2] = [".gitignore", ".luafmtignore"];
+
+// the number of bytes which are read to find the shebang of a file without extension
+const MAX_SHEBANG_LEN: u64 = 256;
+
+/// The rules which skip the files found in the directories, the files passed directly are not filtered
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    /// read the patterns of the `IGNORE_FILES` from the traversed directories and their parents up to the root of
+    /// the git repository
+    pub ignore_files: bool,
+    /// patterns relative to the traversed directory, they take precedence over the ignore files
+    pub excludes: Vec<Glob>,
+    /// if not empty, only the files matching (or placed in a directory matching) one of the patterns are taken
+    pub includes: Vec<Glob>,
+    /// also take the files without extension which start with a lua shebang, e.g. `#!/usr/bin/env lua`
+    pub lua_shebang: bool,
+}
+
+// the patterns of an ignore file are relative to its directory
+struct IgnoreRules {
+    base: PathBuf,
+    globs: Vec<Glob>,
+}
+
+/// Returns the path relative to the `base` with the components separated by `/`, returns `None` if the path is
+/// outside of the `base`
+pub fn rel_path_str(path: &Path, base: &Path) -> Option<String> {
+    let rel_path = path.strip_prefix(base).ok()?;
+    if rel_path.components().any(|c| c == std::path::Component::ParentDir) {
+        return None;
+    }
+    let components: Vec<_> = rel_path.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+    Some(components.join("/"))
+}
+
+// returns `Some(true)` if the last matching pattern excludes the path, `Some(false)` if it is negated
+fn last_match(rules: &[IgnoreRules], path: &Path, is_dir: bool) -> Option<bool> {
+    let mut result = None;
+    for rule in rules {
+        if let Some(rel_path) = rel_path_str(path, &rule.base) {
+            for glob in &rule.globs {
+                if glob.matches(&rel_path, is_dir) {
+                    result = Some(!glob.is_negated());
+                }
+            }
+        }
+    }
+    result
+}
+
+fn read_ignore_rules(dir: &Path, rules: &mut Vec<IgnoreRules>) -> io::Result<()> {
+    for file_name in IGNORE_FILES.iter() {
+        let file_path = dir.join(file_name);
+        if file_path.is_file() {
+            let mut globs = Vec::new();
+            for glob in Glob::parse_list(&fs::read_to_string(&file_path)?) {
+                match glob {
+                    Ok(glob) => globs.push(glob),
+                    Err(err) => eprintln!("Skipping the pattern of `{}`: {}", file_path.display(), err),
+                }
+            }
+            rules.push(IgnoreRules { base: dir.to_path_buf(), globs });
+        }
+    }
+    Ok(())
+}
+
+// the ignore files of the parent directories are taken only inside of a git repository
+fn read_parent_ignore_rules(dir: &Path) -> io::Result<Vec<IgnoreRules>> {
+    let parents: Vec<&Path> = dir.ancestors().skip(1).collect();
+    let mut rules = Vec::new();
+
+    if let Some(repo_pos) = dir.ancestors().position(|path| path.join(".git").exists()) {
+        for parent in parents[..repo_pos].iter().rev() {
+            read_ignore_rules(parent, &mut rules)?;
+        }
+    }
+    Ok(rules)
+}
+
+// the name of the program run by the shebang line, the interpreter may be run by `env`
+fn shebang_program(line: &str) -> Option<String> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let file_name = |word: &str| word.rsplit('/').next().unwrap_or_default().to_string();
+
+    let program = words.next().map(file_name)?;
+    if program != "env" {
+        return Some(program);
+    }
+    // the options and the variables of `env` go before the program
+    words.find(|word| !word.starts_with('-') && !word.contains('=')).map(file_name)
+}
+
+/// Tests whether the first line of a script runs a lua interpreter: `lua`, `luajit`, `lua5.1`, etc. The interpreter may
+/// be run by `env`.
+pub fn is_lua_shebang(line: &str) -> bool {
+    shebang_program(line).map(|program| program.starts_with("lua")) == Some(true)
+}
+
+/// Returns the `lua_version` of the interpreter run by the shebang line, e.g. `luajit` for `#!/usr/bin/env luajit` or
+/// `5.1` for `#!/usr/bin/lua5.1`. Returns `None` if the interpreter does not name a version.
+pub fn shebang_lua_version(line: &str) -> Option<&'static str> {
+    let program = shebang_program(line)?;
+    let version = program.strip_prefix("lua")?;
+    match version {
+        "jit" => Some("luajit"),
+        "u" => Some("luau"),
+        "5.1" | "51" => Some("5.1"),
+        "5.2" | "52" => Some("5.2"),
+        "5.3" | "53" => Some("5.3"),
+        "5.4" | "54" => Some("5.4"),
+        _ if version.starts_with("jit") => Some("luajit"),
+        _ => None,
+    }
+}
+
+/// Tests whether the file starts with a lua shebang
+pub fn has_lua_shebang(path: &Path) -> bool {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+
+    let mut line = String::new();
+    io::BufReader::new(file.take(MAX_SHEBANG_LEN)).read_line(&mut line).is_ok() && is_lua_shebang(&line)
+}
+
+struct Traversal<'a> {
+    recursive: bool,
+    exts: &'a [&'a str],
+    filter_prefix: &'a str,
+    filter: &'a FileFilter,
+    root: PathBuf,
+    cli_rules: Vec<IgnoreRules>,
+    rules: Vec<IgnoreRules>,
+}
+
+impl<'a> Traversal<'a> {
+    fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let excluded = last_match(&self.cli_rules, path, is_dir).or_else(|| last_match(&self.rules, path, is_dir));
+        excluded == Some(true)
+    }
+
+    fn is_included(&self, path: &Path) -> bool {
+        if self.filter.includes.is_empty() {
+            return true;
+        }
+
+        let rel_path = rel_path_str(path, &self.root).unwrap_or_default();
+        self.filter.includes.iter().any(|glob| glob.matches_file(&rel_path))
+    }
+
+    fn has_lua_ext(&self, path: &Path) -> bool {
+        match path.extension().and_then(OsStr::to_str) {
+            Some(ext) => self.exts.contains(&ext),
+            None => self.filter.lua_shebang && has_lua_shebang(path),
+        }
+    }
+
+    // `dir` is the path to read, `abs_dir` is its canonical path to match the patterns against
+    fn visit_dir(&mut self, dir: &Path, abs_dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+        let rules_len = self.rules.len();
+        if self.filter.ignore_files {
+            read_ignore_rules(abs_dir, &mut self.rules)?;
+        }
+
+        for entry in fs::read_dir(dir)? {
             let inner_path = entry?.path();
+            let abs_path = match inner_path.file_name() {
+                Some(file_name) => abs_dir.join(file_name),
+                None => continue,
+            };
 
-            if inner_path.is_dir() && recursive {
-                paths.append(&mut get_path_files(&inner_path, true, ext, filter_prefix)?);
+            if inner_path.is_dir() {
+                if self.recursive
+                    && !(self.filter.ignore_files && abs_path.file_name() == Some(OsStr::new(".git")))
+                    && !self.is_excluded(&abs_path, true)
+                {
+                    self.visit_dir(&inner_path, &abs_path, paths)?;
+                }
             } else if inner_path.is_file()
-                && inner_path.extension().and_then(OsStr::to_str) == Some(ext)
-                && inner_path.file_name().and_then(OsStr::to_str).and_then(|s| Some(!s.starts_with(filter_prefix)))
+                && self.has_lua_ext(&inner_path)
+                && inner_path.file_name().and_then(OsStr::to_str).and_then(|s| Some(!s.starts_with(self.filter_prefix)))
                     == Some(true)
+                && !self.is_excluded(&abs_path, false)
+                && self.is_included(&abs_path)
             {
                 paths.push(inner_path);
             }
         }
-    } else if path.is_file() {
-        paths.push(path.to_path_buf());
-    } else {
+
+        self.rules.truncate(rules_len);
+        Ok(())
+    }
+}
+
+/// Returns the files of the directory (and its subdirectories if `recursive`) which have one of the extensions `exts`
+/// and pass the `filter`, or the path itself if it is a file
+pub fn get_filtered_path_files(
+    path: &PathBuf, recursive: bool, exts: &[&str], filter_prefix: &str, filter: &FileFilter,
+) -> io::Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    } else if !path.is_dir() {
         return Err(Error::new(ErrorKind::Other, ""));
     }
 
+    let root = fs::canonicalize(path)?;
+    let rules = match filter.ignore_files {
+        true => read_parent_ignore_rules(&root)?,
+        false => Vec::new(),
+    };
+    let cli_rules = vec![IgnoreRules { base: root.clone(), globs: filter.excludes.clone() }];
+
+    let mut traversal = Traversal { recursive, exts, filter_prefix, filter, root: root.clone(), cli_rules, rules };
+    let mut paths = Vec::new();
+    traversal.visit_dir(path, &root, &mut paths)?;
+
     Ok(paths)
 }
 
+pub fn get_path_files(path: &PathBuf, recursive: bool, ext: &str, filter_prefix: &str) -> io::Result<Vec<PathBuf>> {
+    get_filtered_path_files(path, recursive, &[ext], filter_prefix, &FileFilter::default())
+}
+
 pub fn test_file_in_dir(path: &Path, file_prefix: &str, file_ext: &str) -> io::Result<Option<PathBuf>> {
     if path.is_dir() {
         for entry in fs::read_dir(path)? {
@@ -45,17 +249,55 @@ pub fn test_file_in_dir(path: &Path, file_prefix: &str, file_ext: &str) -> io::R
     Ok(None)
 }
 
-pub fn get_file_config(file_path: &PathBuf, prefix: &str) -> Option<PathBuf> {
-    match fs::canonicalize(file_path) {
-        Ok(mut cur_file_path) => {
-            while let Some(parent_path) = cur_file_path.parent() {
-                match test_file_in_dir(&parent_path, prefix, "lua") {
-                    Ok(Some(path)) => return Some(path),
-                    _ => cur_file_path = parent_path.to_path_buf(),
-                }
+/// Returns the configuration file of the directory: `.luafmt.toml`, `.luafmt.json`, `luafmt.json` or `.luafmt*.lua`
+/// for the `.luafmt` prefix. Fails with `ErrorKind::InvalidData` if the directory has several of them.
+pub fn find_config_in_dir(path: &Path, prefix: &str) -> io::Result<Option<PathBuf>> {
+    let file_names =
+        [format!("{}.toml", prefix), format!("{}.json", prefix), format!("{}.json", prefix.trim_start_matches('.'))];
+    let mut config_paths: Vec<PathBuf> =
+        file_names.iter().map(|file_name| path.join(file_name)).filter(|file_path| file_path.is_file()).collect();
+
+    let mut lua_paths = Vec::new();
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let inner_path = entry?.path();
+            let file_name = inner_path.file_name().and_then(OsStr::to_str).unwrap_or_default();
+            if inner_path.is_file()
+                && file_name.starts_with(prefix)
+                && inner_path.extension().and_then(OsStr::to_str) == Some("lua")
+            {
+                lua_paths.push(inner_path);
             }
-            None
         }
-        _ => None,
     }
+    lua_paths.sort();
+    config_paths.extend(lua_paths);
+
+    if config_paths.len() > 1 {
+        let names: Vec<_> = config_paths
+            .iter()
+            .map(|file_path| format!("`{}`", file_path.file_name().and_then(OsStr::to_str).unwrap_or_default()))
+            .collect();
+        let msg = format!("Conflicting configuration files in `{}`: {}", path.display(), names.join(", "));
+        return Err(Error::new(ErrorKind::InvalidData, msg));
+    }
+    Ok(config_paths.pop())
+}
+
+/// Iterates over the configuration files of the file directory and all its parent directories, the nearest one goes
+/// first. The directories are read as the iteration goes, the directories with several configuration files fail.
+pub fn file_configs<'a>(file_path: &Path, prefix: &'a str) -> impl Iterator<Item = io::Result<PathBuf>> + 'a {
+    let dir = fs::canonicalize(file_path).ok().and_then(|path| path.parent().map(Path::to_path_buf));
+    iter::successors(dir, |dir| dir.parent().map(Path::to_path_buf)).filter_map(move |dir| {
+        match find_config_in_dir(&dir, prefix) {
+            Ok(Some(path)) => Some(Ok(path)),
+            Err(err) if err.kind() == ErrorKind::InvalidData => Some(Err(err)),
+            // the unreadable directories are skipped
+            Ok(None) | Err(_) => None,
+        }
+    })
+}
+
+pub fn get_file_config(file_path: &Path, prefix: &str) -> io::Result<Option<PathBuf>> {
+    file_configs(file_path, prefix).next().transpose()
 }
@@ -1,21 +1,35 @@
+use std::ffi::OsStr;
 use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 use crate::config;
 use crate::config::{Config, ConfiguredWrite};
 use crate::file_util;
+use crate::formatting::edits;
 use crate::formatting::reconstruction;
 use crate::formatting::util;
+use crate::markdown;
 use crate::parser;
+use crate::parser::lua_ast;
+use crate::parser::structure;
+use crate::parser::Dialect;
+use crate::style;
+
+/// The extension of the markdown files, their lua code blocks are formatted
+pub static MARKDOWN_EXT: &str = "md";
+/// The extension of the Luau files, they are parsed as Luau if `lua_version` is not set
+pub static LUAU_EXT: &str = "luau";
 
 #[derive(Debug)]
 pub enum FormatterError {
     ReadingError,
     NoConfigureFile,
     InvalidConfigFile(String),
-    ParsingError(String),
+    ParsingError(Box<parser::SyntaxError>),
     FormattingError(String),
+    VerificationError(String),
 }
 
 impl fmt::Display for FormatterError {
@@ -27,11 +41,20 @@ impl fmt::Display for FormatterError {
             InvalidConfigFile(conf_fname) => write!(f, "invalid configure file: {}", conf_fname),
             ParsingError(err) => write!(f, "parsing error: {}", err),
             FormattingError(err) => write!(f, "formatting error: {}", err),
+            VerificationError(err) => write!(f, "formatter bug: {}", err),
         }
     }
 }
 
 pub fn process_file(file_path: &PathBuf, cfg: &Config, verbose: bool) -> Result<String, FormatterError> {
+    process_file_with_source(file_path, cfg, verbose).map(|(_, output)| output)
+}
+
+/// Formats the file like `process_file`, returns the source of the file with the output
+pub fn process_file_with_source(
+    file_path: &PathBuf, cfg: &Config, verbose: bool,
+) -> Result<(String, String), FormatterError> {
+    use FormatterError::*;
     if !file_path.is_file() {
         return Err(ReadingError);
     }
@@ -48,40 +71,250 @@ pub fn process_file(file_path: &PathBuf, cfg: &Config, verbose: bool) -> Result<
         _ => return Err(ReadingError),
     }
 
+    let output = process_buffer_of_file(&buffer, file_path, cfg, verbose)?;
+    Ok((buffer, output))
+}
+
+/// Returns the paths and the options of the configuration files found for the file path, the parent files go first.
+/// The search stops at the file with the `root = true` option.
+pub fn get_file_configs(file_path: &Path) -> Result<Vec<(PathBuf, Config)>, FormatterError> {
+    let mut file_configs = Vec::new();
+    // the parent directories above the root file are not read
+    for config_path in file_util::file_configs(file_path, crate::CFG_PREFIX) {
+        let config_path = config_path.map_err(|err| FormatterError::InvalidConfigFile(err.to_string()))?;
+        let file_config =
+            Config::default().reload_format_from_file(&config_path).map_err(FormatterError::InvalidConfigFile)?;
+        let is_root = file_config.root == Some(true);
+        file_configs.push((config_path, file_config));
+        if is_root {
+            break;
+        }
+    }
+
+    file_configs.reverse();
+    Ok(file_configs)
+}
+
+/// Returns the options of the configuration files found for the file path. The nearer files override the options of
+/// the parent ones, the search stops at the file with the `root = true` option. The override sections matching the file
+/// path are applied to the options of their files. Returns `None` if there are no files.
+pub fn get_file_config(file_path: &Path) -> Result<Option<Config>, FormatterError> {
+    let file_configs = get_file_configs(file_path)?;
+    if file_configs.is_empty() {
+        return Ok(None);
+    }
+
+    let mut merged = Config::default();
+    for (_, file_config) in &file_configs {
+        merged.merge(&file_config.for_file(file_path));
+    }
+    Ok(Some(merged))
+}
+
+/// Returns the options which would be used to format the file as the content of a `.luafmt.lua` file. Every option
+/// is commented with its source: the style, the configuration file, its override section or the command line.
+pub fn print_file_config(file_path: &Path, cfg: &Config) -> Result<String, FormatterError> {
+    let mut layers = Vec::new();
+    for (config_path, file_config) in get_file_configs(file_path)? {
+        let config_name = config_path.display().to_string();
+        let overrides: Vec<_> = file_config
+            .matching_overrides(file_path)
+            .into_iter()
+            .map(|over| {
+                let files: Vec<_> = over.files.iter().map(|glob| format!("`{}`", glob.pattern())).collect();
+                (format!("{}, override {}", config_name, files.join(", ")), over.config.clone())
+            })
+            .collect();
+
+        layers.push((config_name, Config { overrides: Vec::new(), ..file_config }));
+        layers.extend(overrides);
+    }
+
+    if layers.is_empty() && cfg.has_empty_format() {
+        return Err(FormatterError::NoConfigureFile);
+    }
+    layers.push(("command line".to_string(), cfg.clone()));
+
+    // the style is the base of all other options
+    let style = layers.iter().rev().find_map(|(_, layer)| layer.style.clone());
+    if let Some(fmt) = style.as_ref().and_then(|name| style::preset(name)) {
+        layers.insert(0, (format!("style `{}`", style.unwrap_or_default()), Config { fmt, ..Config::default() }));
+    }
+
+    let header = format!("-- the effective configuration of `{}`\n", file_path.display());
+    Ok(header + &config::lua_config_with_sources(&layers))
+}
+
+/// Formats the content of the file, which may differ from the content on the disk. The options of the configuration
+/// files are overridden by the options of `cfg`.
+pub fn process_buffer_of_file(
+    content: &String, file_path: &Path, cfg: &Config, verbose: bool,
+) -> Result<String, FormatterError> {
+    process_buffer_of_file_with_dialect(content, file_path, cfg, verbose).map(|(output, _)| output)
+}
+
+/// Formats the content of the file like `process_buffer_of_file`, returns the output with the dialect of the file.
+pub fn process_buffer_of_file_with_dialect(
+    content: &String, file_path: &Path, cfg: &Config, verbose: bool,
+) -> Result<(String, Dialect), FormatterError> {
     use FormatterError::*;
-    match cfg.has_empty_format() {
-        true => match file_util::get_file_config(file_path, crate::CFG_PREFIX) {
-            Some(file_config) => match cfg.reload_format_from_file(&file_config) {
-                Ok(new_cfg) => process_buffer_with_config(&buffer, &new_cfg, verbose),
-                Err(err) => Err(InvalidConfigFile(err)),
-            },
-            None => Err(NoConfigureFile),
-        },
-        false => process_buffer_with_config(&buffer, &cfg, verbose),
+    let process = match is_markdown_file(file_path) {
+        true => process_markdown_with_config,
+        false => process_buffer_with_config,
+    };
+
+    let file_cfg = match get_file_config(file_path) {
+        Ok(Some(mut file_cfg)) => {
+            file_cfg.merge(cfg);
+            Ok(with_file_dialect(file_cfg, file_path, content))
+        }
+        Ok(None) if cfg.has_empty_format() => Err(NoConfigureFile),
+        Ok(None) => Ok(with_file_dialect(cfg.clone(), file_path, content)),
+        Err(err) => Err(err),
+    }?;
+
+    match process(content, &file_cfg, verbose) {
+        Ok(output) => Ok((output, file_cfg.dialect())),
+        Err(ParsingError(err)) => Err(ParsingError(Box::new(err.with_file_name(&file_path.display().to_string())))),
+        Err(err) => Err(err),
+    }
+}
+
+// `.luau` files are Luau, the other files take the dialect of the interpreter of their shebang, e.g. `luajit`
+fn with_file_dialect(mut cfg: Config, file_path: &Path, content: &str) -> Config {
+    if cfg.lua_version.is_some() {
+        return cfg;
     }
+    let version = match file_path.extension().and_then(OsStr::to_str) == Some(LUAU_EXT) {
+        true => Some("luau"),
+        false => content.lines().next().and_then(file_util::shebang_lua_version),
+    };
+    cfg.lua_version = version.map(str::to_string);
+    cfg
 }
 
 pub fn process_buffer_with_config(content: &String, cfg: &Config, verbose: bool) -> Result<String, FormatterError> {
+    let cfg = &cfg.with_style();
     if verbose {
         println!("Format options: {}", cfg);
     }
 
-    use FormatterError::*;
-    match parser::parse_lua(&content) {
-        Ok(mut node_tree) => {
-            let mut outbuffer = String::new();
-            let mut state = config::State::default();
-
-            // process the tree
-            state.pos_range = util::line_range_to_pos_range(&content, cfg.line_range);
-            reconstruction::update_indexes(&content, &mut state);
-            reconstruction::reconstruct_node_tree(&mut node_tree, cfg, &mut state);
-
-            match node_tree.configured_write(&mut outbuffer, &cfg, &content, &mut state) {
-                Ok(_) => Ok(outbuffer),
-                Err(_) => Err(FormattingError(format!("{:?}", node_tree))),
+    let output = format_buffer(content, cfg, verbose)?;
+    if cfg.verify == Some(true) {
+        verify_output(content, &output, cfg)?;
+    }
+
+    Ok(output)
+}
+
+pub fn is_markdown_file(file_path: &Path) -> bool {
+    file_path.extension().and_then(OsStr::to_str) == Some(MARKDOWN_EXT)
+}
+
+/// Formats the ```` ```lua ```` code blocks of the markdown document, the rest of the document is kept as is. The
+/// positions of the syntax errors are counted in the lines of the document.
+pub fn process_markdown_with_config(content: &String, cfg: &Config, verbose: bool) -> Result<String, FormatterError> {
+    // the line range refers to the lines of a lua file
+    let cfg = Config { line_range: None, ..cfg.clone() };
+
+    markdown::format_lua_blocks(content, |code, first_line, indent| {
+        match process_buffer_with_config(&code.to_string(), &cfg, verbose) {
+            Err(FormatterError::ParsingError(mut err)) => {
+                err.line += first_line;
+                err.column += indent.chars().count();
+                err.source_line = format!("{}{}", indent, err.source_line);
+                Err(FormatterError::ParsingError(err))
+            }
+            result => result,
+        }
+    })
+}
+
+fn parse_buffer(content: &str, cfg: &Config, verbose: bool) -> Result<lua_ast::Node, FormatterError> {
+    let parsed = match cfg.error_recovery {
+        Some(true) => parser::parse_lua_with_recovery(content, cfg.dialect()).map(|(node_tree, errors)| {
+            if verbose {
+                for err in errors {
+                    println!("Statement is left unformatted: {}", parser::SyntaxError::from_parse_error(content, err));
+                }
             }
+            node_tree
+        }),
+        _ => parser::parse_lua_with_dialect(content, cfg.dialect()),
+    };
+
+    parsed.map_err(|err| FormatterError::ParsingError(Box::new(parser::SyntaxError::from_parse_error(content, err))))
+}
+
+fn format_buffer(content: &str, cfg: &Config, verbose: bool) -> Result<String, FormatterError> {
+    let mut node_tree = parse_buffer(content, cfg, verbose)?;
+    let mut outbuffer = String::new();
+    let mut state = config::State::default();
+
+    // process the tree
+    state.pos_range = util::line_range_to_pos_range(content, cfg.line_range);
+    reconstruction::update_indexes(content, &mut state);
+    reconstruction::update_directives(content, cfg, &mut state);
+    reconstruction::reconstruct_node_tree(&mut node_tree, cfg, &mut state);
+
+    match node_tree.configured_write(&mut outbuffer, cfg, content, &mut state) {
+        Ok(_) => Ok(outbuffer),
+        Err(_) => Err(FormatterError::FormattingError(format!("{:?}", node_tree))),
+    }
+}
+
+/// Checks that the output has the same syntax tree as the content and that the formatting of the output does not
+/// change it. The positions, the whitespace, the separators and the quotes of the strings are not compared.
+pub fn verify_output(content: &str, output: &str, cfg: &Config) -> Result<(), FormatterError> {
+    use FormatterError::*;
+
+    // the formatting changes the tree, so the content is parsed again
+    let content_tree = parse_buffer(content, cfg, false)?;
+    let output_tree = match parse_buffer(output, cfg, false) {
+        Ok(output_tree) => output_tree,
+        Err(err) => return Err(VerificationError(format!("the output cannot be parsed, {}", err))),
+    };
+
+    let content_items = structure::structure(&content_tree, content);
+    let output_items = structure::structure(&output_tree, output);
+    if let Some((content_item, output_item)) = structure::first_difference(&content_items, &output_items) {
+        let describe = |item: Option<&structure::StructureItem>, buf: &str| match item {
+            Some(item) => format!("`{}` at line {}", item.label, util::pos_to_line(buf, item.loc.0)),
+            None => "nothing".to_string(),
+        };
+        return Err(VerificationError(format!(
+            "the syntax tree of the output differs, {} became {}",
+            describe(content_item, content),
+            describe(output_item, output)
+        )));
+    }
+
+    // the lines of the range move when the lines before them are changed
+    if cfg.line_range.is_none() {
+        let second_output = match format_buffer(output, cfg, false) {
+            Ok(second_output) => second_output,
+            Err(err) => return Err(VerificationError(format!("the output cannot be formatted again, {}", err))),
+        };
+
+        if second_output != output {
+            let line = output.lines().zip(second_output.lines()).take_while(|(l, r)| l == r).count() + 1;
+            return Err(VerificationError(format!("the second formatting changes the output at line {}", line)));
         }
-        Err(err) => Err(ParsingError(format!("{}", err))),
     }
+
+    Ok(())
+}
+
+/// Returns the edits which turn the content into the formatted one, the ranges are counted in chars of the content.
+pub fn process_buffer_to_edits(
+    content: &String, cfg: &Config, verbose: bool,
+) -> Result<Vec<(Range<usize>, String)>, FormatterError> {
+    let output = process_buffer_with_config(content, cfg, verbose)?;
+    Ok(edits::text_edits(content, &output, cfg.dialect()))
+}
+
+/// Returns the edits which turn the `source` into the `output`, the ranges are counted in chars of the source. The
+/// texts are split into the tokens of the `dialect`.
+pub fn text_edits(source: &str, output: &str, dialect: Dialect) -> Vec<(Range<usize>, String)> {
+    edits::text_edits(source, output, dialect)
 }
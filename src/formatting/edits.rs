@@ -0,0 +1,133 @@
+use std::ops::Range;
+
+use crate::diff::{self, Edit};
+use crate::parser;
+use crate::parser::common::Loc;
+use crate::parser::Dialect;
+
+struct Text<'a> {
+    buf: &'a str,
+    chars_to_bytes: Vec<usize>,
+    tokens: Vec<Loc>,
+}
+
+impl<'a> Text<'a> {
+    fn new(buf: &'a str, dialect: Dialect) -> Option<Self> {
+        let chars_to_bytes = buf.char_indices().map(|(b, _)| b).chain(std::iter::once(buf.len())).collect();
+        let tokens = parser::token_locs(buf, dialect)?;
+
+        Some(Text { buf, chars_to_bytes, tokens })
+    }
+
+    fn len(&self) -> usize {
+        self.chars_to_bytes.len() - 1
+    }
+
+    fn substr(&self, range: Range<usize>) -> &'a str {
+        &self.buf[self.chars_to_bytes[range.start]..self.chars_to_bytes[range.end]]
+    }
+}
+
+// the changed part of the region is replaced, the common prefix and suffix are kept
+fn push_edit(edits: &mut Vec<(Range<usize>, String)>, old: &str, old_start: usize, new: &str) {
+    if old == new {
+        return;
+    }
+
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+    let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+
+    let range = old_start + prefix..old_start + old.len() - suffix;
+    edits.push((range, new[prefix..new.len() - suffix].iter().collect()));
+}
+
+/// Returns the edits which turn `source` into `output`, the ranges are counted in chars of the source. The tokens
+/// of both texts are matched and only the differing parts between the matched tokens are replaced.
+pub fn text_edits(source: &str, output: &str, dialect: Dialect) -> Vec<(Range<usize>, String)> {
+    let (src, out) = match (Text::new(source, dialect), Text::new(output, dialect)) {
+        (Some(src), Some(out)) => (src, out),
+        _ => {
+            let mut edits = Vec::new();
+            push_edit(&mut edits, source, 0, output);
+            return edits;
+        }
+    };
+
+    let src_tokens: Vec<&str> = src.tokens.iter().map(|loc| src.substr(loc.0..loc.1)).collect();
+    let out_tokens: Vec<&str> = out.tokens.iter().map(|loc| out.substr(loc.0..loc.1)).collect();
+
+    // the regions between the matched tokens are compared, the end of text is matched too
+    let matched = diff::diff_items(&src_tokens, &out_tokens).into_iter().filter_map(|edit| match edit {
+        Edit::Equal(i, j) => Some((src.tokens[i].clone(), out.tokens[j].clone())),
+        _ => None,
+    });
+    let text_end = (Loc(src.len(), src.len()), Loc(out.len(), out.len()));
+
+    let mut edits = Vec::new();
+    let (mut src_pos, mut out_pos) = (0, 0);
+    for (src_loc, out_loc) in matched.chain(std::iter::once(text_end)) {
+        push_edit(&mut edits, src.substr(src_pos..src_loc.0), src_pos, out.substr(out_pos..out_loc.0));
+        src_pos = src_loc.1;
+        out_pos = out_loc.1;
+    }
+
+    edits
+}
+
+#[cfg(test)]
+fn apply_edits(source: &str, edits: &[(Range<usize>, String)]) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut result = String::new();
+    let mut pos = 0;
+    for (range, replacement) in edits {
+        result.extend(&chars[pos..range.start]);
+        result.push_str(replacement);
+        pos = range.end;
+    }
+    result.extend(&chars[pos..]);
+    result
+}
+
+#[test]
+fn test_text_edits() {
+    let ts = |source: &str, output: &str| {
+        let edits = text_edits(source, output, Dialect::Lua54);
+        assert_eq!(apply_edits(source, &edits), output);
+        edits
+    };
+
+    assert_eq!(ts("a = 1\n", "a = 1\n"), vec![]);
+    assert_eq!(ts("a=1\n", "a = 1\n"), vec![(1..1, " ".to_string()), (2..2, " ".to_string())]);
+    assert_eq!(
+        ts("local  x  =  'ё'", "local x = 'ё'"),
+        vec![(6..7, String::new()), (9..10, String::new()), (12..13, String::new())]
+    );
+
+    // the changed tokens are replaced as well
+    assert_eq!(ts("t = {1;2}", "t = {1, 2,}"), vec![(6..7, ", ".to_string()), (8..8, ",".to_string())]);
+    assert_eq!(ts("s = 'str'", "s = \"str\""), vec![(4..9, "\"str\"".to_string())]);
+
+    // comments are in the regions between tokens
+    assert_eq!(ts("a = 1 --comment\nb = 2", "a = 1 -- comment\nb = 2"), vec![(8..8, " ".to_string())]);
+    assert_eq!(ts("a = 1\n\n\n\nb = 2", "a = 1\n\nb = 2"), vec![(7..9, String::new())]);
+
+    // the texts which cannot be split into tokens are compared as a whole
+    assert_eq!(ts("a = 'str", "a = 'str"), vec![]);
+    assert_eq!(ts("a = 'str", "a  = 'str"), vec![(2..2, " ".to_string())]);
+
+    // the tokens of large files are matched in linear memory
+    let source = (0..20_000).map(|i| format!("a{}=f(i,{})\n", i, i)).collect::<String>();
+    let output = (0..20_000).map(|i| format!("a{} = f(i, {})\n", i, i)).collect::<String>();
+    assert_eq!(ts(&source, &output).len(), 60_000);
+
+    // the tokens of the dialect are matched
+    let source = "local s=`{a}`\nx=1";
+    let output = "local s = `{a}`\nx = 1";
+    assert_eq!(text_edits(source, output, Dialect::Lua54), vec![(7..16, " = `{a}`\nx = ".to_string())]);
+    assert_eq!(
+        text_edits(source, output, Dialect::Luau),
+        vec![(7..7, " ".to_string()), (8..8, " ".to_string()), (15..15, " ".to_string()), (16..16, " ".to_string())]
+    );
+}
@@ -60,7 +60,9 @@ where
                 let item = &items[i];
                 write!(f, "{}", get_sep(prev_item_tp))?;
 
-                if util::test_out_of_range(&state.pos_range, &item.0) {
+                if util::test_out_of_range(&state.pos_range, &item.0)
+                    || util::test_disabled(&state.disabled_ranges, &item.0)
+                {
                     cfg_write!(f, cfg, buf, state, item.0)?;
                 } else {
                     let need_newline = list_node.need_newlines(cfg)
@@ -109,7 +111,9 @@ where
             for i in 1..items.len() {
                 let item = &items[i];
 
-                if util::test_out_of_range(&state.pos_range, &item.0) {
+                if util::test_out_of_range(&state.pos_range, &item.0)
+                    || util::test_disabled(&state.disabled_ranges, &item.0)
+                {
                     cfg_write!(f, cfg, buf, state, item.0)?;
                 } else {
                     let prev_item_tp = &items[i - 1];
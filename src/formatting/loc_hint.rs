@@ -36,11 +36,13 @@ impl<'a, 'b> LocHintConstructible<'a, 'b> for SpaceLocHint<'a, 'b> {
 }
 
 impl CommentLocHint<'_, '_> {
-    fn write_formatted_comment_block(&self, f: &mut String, cfg: &Config, _buf: &str, comment_block: &str) -> std::fmt::Result {
+    fn write_formatted_comment_block(
+        &self, f: &mut String, cfg: &Config, buf: &str, state: &State, comment_block: &str,
+    ) -> std::fmt::Result {
 
         // if `comment_block` is empty
         if comment_block.is_empty() {
-            if cfg.fmt.replace_zero_spaces_with_hint == Some(true) {
+            if cfg.fmt.replace_zero_spaces_with_hint == Some(true) && !self.is_optional_space(cfg, buf, state) {
                 write!(f, "{}", self.1)?;
             }
             return Ok(());
@@ -60,6 +62,20 @@ impl CommentLocHint<'_, '_> {
 
         Ok(())
     }
+
+    // the tokens around the space hint are taken from the source
+    fn is_optional_space(&self, cfg: &Config, buf: &str, state: &State) -> bool {
+        if cfg.fmt.remove_optional_spaces != Some(true) || !self.1.chars().all(|ch| ch == ' ') {
+            return false;
+        }
+
+        let left = Loc(self.0.0.saturating_sub(1), self.0.0).substr(buf, state, 0).chars().next();
+        let right = Loc(self.0.1, self.0.1 + 1).substr(buf, state, 0).chars().next();
+        match (left, right) {
+            (Some(left), Some(right)) => !util::tokens_merge(left, right),
+            _ => true,
+        }
+    }
 }
 
 impl ConfiguredWrite for CommentLocHint<'_, '_> {
@@ -79,7 +95,7 @@ impl ConfiguredWrite for CommentLocHint<'_, '_> {
                 formatted_comment_block.push(f.chars().last().unwrap_or(' '));
 
                 match node_tree.configured_write(&mut formatted_comment_block, cfg, &comment_buffer, state) {
-                    Ok(_) => self.write_formatted_comment_block(f, cfg, buf, &formatted_comment_block[1..]),
+                    Ok(_) => self.write_formatted_comment_block(f, cfg, buf, state, &formatted_comment_block[1..]),
                     Err(err) => Err(err),
                 }
             }
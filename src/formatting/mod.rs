@@ -1,4 +1,5 @@
 pub mod decoration;
+pub mod edits;
 pub mod list;
 pub mod loc_hint;
 pub mod reconstruction;
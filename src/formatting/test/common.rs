@@ -1,6 +1,6 @@
 use crate::config::*;
 use crate::formatting::reconstruction;
-use crate::parser::parse_lua;
+use crate::parser::{parse_lua_with_dialect, parse_lua_with_recovery};
 
 #[allow(dead_code)]
 #[derive(PartialEq, Debug)]
@@ -11,12 +11,18 @@ pub enum TestError {
 
 #[allow(dead_code)]
 pub fn ts_base(source: &str, cfg: &Config) -> Result<String, TestError> {
-    match parse_lua(source) {
+    let parsed = match cfg.error_recovery {
+        Some(true) => parse_lua_with_recovery(source, cfg.dialect()).map(|(node_tree, _)| node_tree),
+        _ => parse_lua_with_dialect(source, cfg.dialect()),
+    };
+
+    match parsed {
         Err(_) => Err(TestError::ErrorWhileParsing),
         Ok(mut node_tree) => {
             let mut state = State::default();
 
             reconstruction::update_indexes(&source, &mut state);
+            reconstruction::update_directives(&source, cfg, &mut state);
             // println!("{:?}", state.chars_to_bytes);
             reconstruction::reconstruct_node_tree(&mut node_tree, cfg, &mut state);
 
@@ -0,0 +1,87 @@
+use super::common::*;
+use crate::config::*;
+
+#[test]
+fn test_directive_off_on() {
+    let cfg = Config {
+        fmt: FormatOpts {
+            replace_zero_spaces_with_hint: Some(true),
+            remove_spaces_between_tokens: Some(true),
+            hint_before_comment: Some(" ".to_string()),
+            ..FormatOpts::default()
+        },
+        ..Config::default()
+    };
+    let ts = |s: &str| ts_base(s, &cfg);
+
+    assert_eq!(
+        ts("a=1\n-- luafmt: off\nb  =  2\n-- luafmt: on\nc=3"),
+        Ok("a = 1\n-- luafmt: off\nb  =  2\n-- luafmt: on\nc = 3".to_string())
+    );
+    assert_eq!(
+        ts("a=1\n--luafmt:off\nlocal t = {\n  1,   2,\n  30, 4,\n}\nb  =  2\n--luafmt:on\nc=3"),
+        Ok("a = 1\n--luafmt:off\nlocal t = {\n  1,   2,\n  30, 4,\n}\nb  =  2\n--luafmt:on\nc = 3".to_string())
+    );
+
+    // without the closing directive formatting is disabled till the end of file
+    assert_eq!(
+        ts("a=1\n--[[luafmt: off]] b  =  2\nc  =  3  "),
+        Ok("a = 1\n--[[luafmt: off]] b  =  2\nc  =  3  ".to_string())
+    );
+
+    // the whitespace around the directives inside the region is kept
+    assert_eq!(
+        ts("a=1\n--[[luafmt: off]]  b  =  2  --[[luafmt: on]]\nc=3"),
+        Ok("a = 1\n--[[luafmt: off]]  b  =  2  --[[luafmt: on]]\nc = 3".to_string())
+    );
+
+    // nested blocks
+    assert_eq!(
+        ts("function f()\na=1\n-- luafmt: off\nb  =  2\n-- luafmt: on\nc=3\nend"),
+        Ok("function f()\na = 1\n-- luafmt: off\nb  =  2\n-- luafmt: on\nc = 3\nend".to_string())
+    );
+
+    // not a directive
+    assert_eq!(ts("a=1\n-- luafmt: of\nb  =  2"), Ok("a = 1\n-- luafmt: of\nb = 2".to_string()));
+    assert_eq!(ts("a=1\n-- luafmt off\nb  =  2"), Ok("a = 1\n-- luafmt off\nb = 2".to_string()));
+}
+
+#[test]
+fn test_directive_ignore_next() {
+    let cfg = Config {
+        fmt: FormatOpts {
+            replace_zero_spaces_with_hint: Some(true),
+            remove_spaces_between_tokens: Some(true),
+            hint_before_comment: Some(" ".to_string()),
+            ..FormatOpts::default()
+        },
+        ..Config::default()
+    };
+    let ts = |s: &str| ts_base(s, &cfg);
+
+    assert_eq!(
+        ts("a=1\n-- luafmt: ignore-next\nb  =  { 1,\n      22 }\nc=3"),
+        Ok("a = 1\n-- luafmt: ignore-next\nb  =  { 1,\n      22 }\nc = 3".to_string())
+    );
+    assert_eq!(
+        ts("-- luafmt: ignore-next\nlocal   t\nif a then\nb  =  2\nend"),
+        Ok(" -- luafmt: ignore-next\nlocal   t\nif a then\nb = 2\nend".to_string())
+    );
+
+    // a compound statement is ignored as a whole, the directive also works inside nested blocks
+    assert_eq!(
+        ts("a=1 -- luafmt: ignore-next\nfor i=1,2 do\nb  =  2 end\nc=3"),
+        Ok("a = 1 -- luafmt: ignore-next\nfor i=1,2 do\nb  =  2 end\nc = 3".to_string())
+    );
+    assert_eq!(
+        ts("for i=1,2 do -- luafmt: ignore-next\nb  =  2\nc  =  3 end"),
+        Ok("for i = 1, 2 do -- luafmt: ignore-next\nb  =  2\nc = 3 end".to_string())
+    );
+    assert_eq!(
+        ts("function f() -- luafmt: ignore-next\nreturn  a,b end"),
+        Ok("function f() -- luafmt: ignore-next\nreturn  a,b end".to_string())
+    );
+
+    // no statement after the directive
+    assert_eq!(ts("a  =  1 -- luafmt: ignore-next\n"), Ok("a = 1 -- luafmt: ignore-next\n".to_string()));
+}
@@ -0,0 +1,42 @@
+use super::common::*;
+use crate::config::*;
+
+fn ts_version(source: &str, version: &str) -> Result<String, TestError> {
+    let cfg = Config {
+        lua_version: Some(version.to_string()),
+        fmt: FormatOpts {
+            replace_zero_spaces_with_hint: Some(true),
+            remove_spaces_between_tokens: Some(true),
+            ..FormatOpts::default()
+        },
+        ..Config::default()
+    };
+    ts_base(source, &cfg)
+}
+
+#[test]
+fn test_lua_version() {
+    let ts = |s: &str| ts_version(s, "luajit");
+    assert_eq!(ts("local x=1ULL+0x7fLL*2i"), Ok("local x = 1ULL + 0x7fLL * 2i".to_string()));
+    assert_eq!(ts("goto continue\n::continue::"), Ok("goto continue\n::continue::".to_string()));
+    assert_eq!(ts("local x=a//b"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(ts("local x=a&b"), Err(TestError::ErrorWhileParsing));
+
+    let ts = |s: &str| ts_version(s, "5.1");
+    assert_eq!(ts("local goto=1"), Ok("local goto = 1".to_string()));
+    assert_eq!(ts("goto l"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(ts("::l::"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(ts("local x=a<<b"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(ts("local x=1LL"), Err(TestError::ErrorWhileParsing));
+
+    let ts = |s: &str| ts_version(s, "5.2");
+    assert_eq!(ts("goto l\n::l::"), Ok("goto l\n::l::".to_string()));
+    assert_eq!(ts("local x=~a"), Err(TestError::ErrorWhileParsing));
+
+    let ts = |s: &str| ts_version(s, "5.3");
+    assert_eq!(ts("local x=a//b|~c<<2"), Ok("local x = a // b | ~c << 2".to_string()));
+    assert_eq!(ts("local x<const> =1"), Err(TestError::ErrorWhileParsing));
+
+    let ts = |s: &str| ts_version(s, "5.4");
+    assert_eq!(ts("local x<const> =a>>1"), Ok("local x <const> = a >> 1".to_string()));
+}
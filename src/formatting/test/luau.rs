@@ -0,0 +1,105 @@
+use super::common::*;
+use crate::config::*;
+
+#[test]
+fn test_luau() {
+    let cfg = Config {
+        lua_version: Some("luau".to_string()),
+        fmt: FormatOpts {
+            replace_zero_spaces_with_hint: Some(true),
+            remove_spaces_between_tokens: Some(true),
+            ..FormatOpts::default()
+        },
+        ..Config::default()
+    };
+    let ts = |s: &str| ts_base(s, &cfg);
+
+    assert_eq!(ts("a+=1\nb  ..=  'x'\nc//=2"), Ok("a += 1\nb ..= 'x'\nc //= 2".to_string()));
+    assert_eq!(ts("t.x-=f( 1 )"), Ok("t.x -= f(1)".to_string()));
+    assert_eq!(
+        ts("for i=1,3 do if i==2 then continue end end"),
+        Ok("for i = 1, 3 do if i == 2 then continue end end".to_string())
+    );
+    assert_eq!(ts("while a do f()continue;end"), Ok("while a do f() continue; end".to_string()));
+    // `continue` is a keyword only in the statement position
+    assert_eq!(ts("local continue=1"), Ok("local continue = 1".to_string()));
+    assert_eq!(ts("continue=1"), Ok("continue = 1".to_string()));
+    assert_eq!(ts("obj:continue( )"), Ok("obj:continue()".to_string()));
+    assert_eq!(ts("local x=if a then 1 else 2"), Ok("local x = if a then 1 else 2".to_string()));
+    assert_eq!(
+        ts("local x=if a then 1 elseif b then 2 elseif c then 3 else 4"),
+        Ok("local x = if a then 1 elseif b then 2 elseif c then 3 else 4".to_string())
+    );
+    assert_eq!(ts("print(`a {b} c`)"), Ok("print(`a {b} c`)".to_string()));
+    assert_eq!(ts("local s=`{ `{x}` }`..'y'"), Ok("local s = `{ `{x}` }` .. 'y'".to_string()));
+
+    // the directives of the type checker are not changed by the comment options
+    let cfg = Config {
+        fmt: FormatOpts { hint_before_oneline_comment_text: Some(" ".to_string()), ..cfg.fmt.clone() },
+        ..cfg.clone()
+    };
+    let ts = |s: &str| ts_base(s, &cfg);
+    assert_eq!(
+        ts("--!strict
+--x
+a=1"),
+        Ok("--!strict
+-- x
+a = 1"
+            .to_string())
+    );
+}
+
+#[test]
+fn test_luau_types() {
+    let cfg = Config {
+        lua_version: Some("luau".to_string()),
+        fmt: FormatOpts {
+            replace_zero_spaces_with_hint: Some(true),
+            remove_spaces_between_tokens: Some(true),
+            ..FormatOpts::default()
+        },
+        ..Config::default()
+    };
+    let ts = |s: &str| ts_base(s, &cfg);
+
+    assert_eq!(ts("local x:number=1"), Ok("local x: number = 1".to_string()));
+    assert_eq!(ts("local x : string? , y:T<U>"), Ok("local x: string?, y: T<U>".to_string()));
+    assert_eq!(
+        ts("function f(a:number,b:string?,...:any):boolean return true end"),
+        Ok("function f(a: number, b: string?, ...: any): boolean return true end".to_string())
+    );
+    assert_eq!(
+        ts("local f=function<T>(x:T):T return x end"),
+        Ok("local f = function<T>(x: T): T return x end".to_string())
+    );
+    assert_eq!(ts("function f():(number,string) end"), Ok("function f(): (number, string) end".to_string()));
+    assert_eq!(ts("type  Point={x:number,y:number}"), Ok("type Point = {x: number, y: number}".to_string()));
+    assert_eq!(ts("export type Map<K,V> ={[K]:V}"), Ok("export type Map<K, V> = {[K]: V}".to_string()));
+    assert_eq!(ts("type F=(number,...string)->...any"), Ok("type F = (number, ...string) -> ...any".to_string()));
+    assert_eq!(ts("type U=A|B&C|nil|'s'|true"), Ok("type U = A | B & C | nil | 's' | true".to_string()));
+    assert_eq!(ts("type T=typeof(x.y)"), Ok("type T = typeof(x.y)".to_string()));
+    assert_eq!(ts("type M=mod.Type<number>"), Ok("type M = mod.Type<number>".to_string()));
+    assert_eq!(ts("local y=x::number"), Ok("local y = x :: number".to_string()));
+    assert_eq!(ts("local y=(x::any)::T?"), Ok("local y = (x :: any) :: T?".to_string()));
+
+    // the names of the Luau keywords stay names in other places
+    assert_eq!(ts("type=1\nexport=type"), Ok("type = 1\nexport = type".to_string()));
+    assert_eq!(ts("local goto=1"), Ok("local goto = 1".to_string()));
+}
+
+#[test]
+fn test_luau_in_lua() {
+    let cfg = Config::default();
+    let ts = |s: &str| ts_base(s, &cfg);
+
+    assert_eq!(ts("a += 1"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(ts("local x: number = 1"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(ts("type T = number"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(ts("local x = if a then 1 else 2"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(ts("print(`a`)"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(ts("function f<T>(x) end"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(ts("goto l ::l::"), Ok("goto l ::l::".to_string()));
+    assert_eq!(ts("local continue = 1"), Ok("local continue = 1".to_string()));
+    assert_eq!(ts("while a do continue end"), Err(TestError::ErrorWhileParsing));
+}
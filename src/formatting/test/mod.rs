@@ -1,10 +1,14 @@
 mod common;
 
 mod comment_options;
+mod directives;
 mod field_options;
 mod indent_options;
+mod lua_version;
+mod luau;
 mod max_width;
 mod other;
+mod recovery;
 mod remove_comments;
 mod spaces_between_tokens;
 mod table;
@@ -0,0 +1,38 @@
+use super::common::*;
+use crate::config::*;
+
+#[test]
+fn test_error_recovery() {
+    let cfg = Config {
+        error_recovery: Some(true),
+        fmt: FormatOpts {
+            replace_zero_spaces_with_hint: Some(true),
+            remove_spaces_between_tokens: Some(true),
+            ..FormatOpts::default()
+        },
+        ..Config::default()
+    };
+    let ts = |s: &str| ts_base(s, &cfg);
+
+    assert_eq!(ts("a=1\nif a then b = end\nc  =  2\n"), Ok("a = 1\nif a then b = end\nc = 2\n".to_string()));
+    assert_eq!(ts("f(  )\n)\ng(  )"), Ok("f()\n)\ng()".to_string()));
+
+    // the whole top-level statement is kept as is, even if the error is in a nested block
+    assert_eq!(
+        ts("local x=1\nfunction f()\n  x = = 1\n  y=2\nend\nprint( x )\n"),
+        Ok("local x = 1\nfunction f()\n  x = = 1\n  y=2\nend\nprint(x)\n".to_string())
+    );
+    assert_eq!(ts("return function() x = = 1 end"), Ok("return function() x = = 1 end".to_string()));
+
+    // the parser skips tokens till the next statement
+    assert_eq!(ts("x  =  1\nlocal t = {1,2\ny=3\n"), Ok("x = 1\nlocal t = {1,2\ny = 3\n".to_string()));
+    assert_eq!(ts("a  =  b  =  1\nc=2"), Ok("a = b =  1\nc = 2".to_string()));
+    assert_eq!(ts("a=1\nfunction f(a)\n  print( a )\n"), Ok("a = 1\nfunction f(a)\n  print( a )\n".to_string()));
+
+    // lexical errors cannot be recovered
+    assert_eq!(ts("a=1\nb = 'string\n"), Err(TestError::ErrorWhileParsing));
+
+    // without the option the file is not formatted at all
+    let cfg = Config { error_recovery: None, ..cfg };
+    assert_eq!(ts_base("a=1\nif a then b = end\nc  =  2\n", &cfg), Err(TestError::ErrorWhileParsing));
+}
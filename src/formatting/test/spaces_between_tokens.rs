@@ -340,3 +340,38 @@ fn test_spaces_between_tokens_special() {
         Ok(" --123\n#!/usr/bin/lua\n --[[1]] print(a) --2\n".to_string())
     );
 }
+
+#[test]
+fn test_spaces_between_tokens_attrib() {
+    let cfg = Config {
+        fmt: FormatOpts {
+            replace_zero_spaces_with_hint: Some(true),
+            remove_spaces_between_tokens: Some(true),
+            hint_before_comment: Some(" ".to_string()),
+            hint_after_multiline_comment: Some(" ".to_string()),
+            ..FormatOpts::default()
+        },
+        ..Config::default()
+    };
+    let ts = |s: &'static str| ts_base(s, &cfg);
+
+    assert_eq!(ts("local a<const> =1"), Ok("local a <const> = 1".to_string()));
+    assert_eq!(ts("local a  <  const  >  , b<close>"), Ok("local a <const>, b <close>".to_string()));
+    assert_eq!(
+        ts("local a--1\n<--[[2]]const--[[3]]>--4\n= 1"),
+        Ok("local a --1\n< --[[2]] const --[[3]] > --4\n= 1".to_string())
+    );
+
+    let cfg = Config {
+        fmt: FormatOpts {
+            hint_before_attrib: Some("".to_string()),
+            hint_inside_attrib: Some(" ".to_string()),
+            ..cfg.fmt
+        },
+        ..Config::default()
+    };
+    let ts = |s: &'static str| ts_base(s, &cfg);
+
+    assert_eq!(ts("local a <const> = 1"), Ok("local a< const > = 1".to_string()));
+    assert_eq!(ts("local a<close>, b <const>"), Ok("local a< close >, b< const >".to_string()));
+}
@@ -5,7 +5,7 @@ use crate::parser::common::Loc;
 #[macro_export]
 macro_rules! out_of_range_write {
     ($wrt: expr, $cfg: expr, $buf: expr, $state: expr, $span: expr, $($arg:expr),+) => {{
-        if util::test_out_of_range(&$state.pos_range, $span) {
+        if util::test_out_of_range(&$state.pos_range, $span) || util::test_disabled(&$state.disabled_ranges, $span) {
             return write!($wrt, "{}", $span.substr($buf, $state, 0));
         } else if util::test_not_completely_contained(&$state.pos_range, $span) {
             return cfg_write!($wrt, $cfg, $buf, $state, $($arg),+);
@@ -16,7 +16,7 @@ macro_rules! out_of_range_write {
 #[macro_export]
 macro_rules! out_of_range_only_write {
     ($wrt: expr, $cfg: expr, $buf: expr, $state: expr, $span: expr) => {{
-        if util::test_out_of_range(&$state.pos_range, $span) {
+        if util::test_out_of_range(&$state.pos_range, $span) || util::test_disabled(&$state.disabled_ranges, $span) {
             return write!($wrt, "{}", $span.substr($buf, $state, 0));
         }
     }};
@@ -25,8 +25,11 @@ macro_rules! out_of_range_only_write {
 #[macro_export]
 macro_rules! out_of_range_comment_only_write {
     ($wrt: expr, $cfg: expr, $buf: expr, $state: expr, $span: expr) => {{
-        if util::test_out_of_range(&$state.comment_pos_range, $span) {
-            return write!($wrt, "{}", $span.substr($buf, $state, $state.comment_offset));
+        let offset = $state.comment_offset;
+        if util::test_out_of_range(&$state.comment_pos_range, $span)
+            || util::test_disabled(&$state.disabled_ranges, &Loc($span.0 + offset, $span.1 + offset))
+        {
+            return write!($wrt, "{}", $span.substr($buf, $state, offset));
         }
     }};
 }
@@ -84,6 +87,11 @@ pub fn test_not_completely_contained(range: &Option<(usize, usize)>, span: &Loc)
     return false;
 }
 
+// the span is completely contained in a region disabled with the `luafmt:` directives
+pub fn test_disabled(ranges: &[(usize, usize)], span: &Loc) -> bool {
+    ranges.iter().any(|&(l, r)| span.0 >= l && span.1 <= r)
+}
+
 pub fn trim_end_spaces_and_tabs<'a>(string: &'a String) -> &'a str {
     string.trim_end_matches(|ch: char| return ch == ' ' || ch == '\t')
 }
@@ -100,6 +108,20 @@ pub fn has_newlines(s: &str) -> bool {
     return s.find('\n').is_some();
 }
 
+/// Checks that the adjacent tokens would be read as other tokens without a space between them, e.g. `local x`,
+/// `a - -b` (a comment) or `1 ..` (a malformed number)
+pub fn tokens_merge(left: char, right: char) -> bool {
+    let is_word = |ch: char| ch.is_alphanumeric() || ch == '_';
+    match (left, right) {
+        (l, r) if is_word(l) && is_word(r) => true,
+        (l, '.') => l.is_ascii_digit() || l == '.',
+        ('.', r) => r.is_ascii_digit(),
+        ('-', '-') | ('[', '[') | ('[', '=') | ('/', '/') | ('<', '<') | ('>', '>') | (':', ':') => true,
+        ('=' | '~' | '<' | '>', '=') => true,
+        _ => false,
+    }
+}
+
 pub fn charstring_to_normalstring(s: &str) -> String {
     let mut result = String::new();
     let mut escaped = false;
@@ -147,6 +169,11 @@ pub fn line_range_to_pos_range(buf: &str, lr_opt: Option<(usize, usize)>) -> Opt
     None
 }
 
+/// Returns the number of the line of the char position, the lines are counted from 1
+pub fn pos_to_line(buf: &str, pos: usize) -> usize {
+    buf.chars().take(pos).filter(|&ch| ch == '\n').count() + 1
+}
+
 #[test]
 fn test_line_range_to_pos_range() {
     let source = r#"/usr/bin/lua
@@ -178,6 +205,16 @@ print(123)"#;
     assert_eq!(line_range_to_pos_range(&source, Some((3, 6))), Some((13, 73)));
 }
 
+#[test]
+fn test_test_disabled() {
+    assert!(!test_disabled(&[], &Loc(1, 2)));
+    assert!(test_disabled(&[(1, 5)], &Loc(1, 5)));
+    assert!(test_disabled(&[(1, 5)], &Loc(2, 3)));
+    assert!(!test_disabled(&[(1, 5)], &Loc(0, 3)));
+    assert!(!test_disabled(&[(1, 5)], &Loc(3, 6)));
+    assert!(test_disabled(&[(1, 5), (7, 9)], &Loc(7, 8)));
+}
+
 #[test]
 fn test_trim_end_spaces_and_tabs() {
     assert_eq!(trim_end_spaces_and_tabs(&"abc\t  \t  ".to_string()), "abc");
@@ -218,3 +255,19 @@ fn test_charstring_to_normalstring() {
     assert_eq!(charstring_to_normalstring(r#" hi ab\\"cas\\"das   "#), r#" hi ab\\\"cas\\\"das   "#);
     assert_eq!(charstring_to_normalstring(r#" hi ab\\\"cas\\\"das   "#), r#" hi ab\\\"cas\\\"das   "#);
 }
+
+#[test]
+fn test_tokens_merge() {
+    assert!(tokens_merge('l', 'x'));
+    assert!(tokens_merge('1', 'e'));
+    assert!(tokens_merge('-', '-'));
+    assert!(tokens_merge('1', '.'));
+    assert!(tokens_merge('.', '.'));
+    assert!(tokens_merge('.', '5'));
+    assert!(tokens_merge('[', '['));
+    assert!(tokens_merge('=', '='));
+    assert!(!tokens_merge('x', '='));
+    assert!(!tokens_merge('=', '{'));
+    assert!(!tokens_merge(')', 'x'));
+    assert!(!tokens_merge('"', '.'));
+}
@@ -0,0 +1,212 @@
+use regex::Regex;
+
+/// Gitignore-style glob pattern. The pattern without a slash matches the file name at any level, otherwise it is
+/// matched against the whole path relative to the base directory. `*` and `?` do not match `/`, `**` matches any
+/// number of directories, the trailing slash matches only directories and the leading `!` negates the pattern.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    pattern: String,
+    regex: Regex,
+    negated: bool,
+    dir_only: bool,
+}
+
+fn push_class(regex: &mut String, chars: &[char]) -> usize {
+    // returns the number of consumed chars, the unclosed bracket is a literal
+    let mut i = 1;
+    if let Some('!') | Some('^') = chars.get(i) {
+        i += 1;
+    }
+    if chars.get(i) == Some(&']') {
+        i += 1;
+    }
+    while i < chars.len() && chars[i] != ']' {
+        i += 1;
+    }
+    if i >= chars.len() {
+        regex.push_str(r"\[");
+        return 1;
+    }
+
+    regex.push('[');
+    let mut j = 1;
+    if let '!' | '^' = chars[j] {
+        regex.push('^');
+        j += 1;
+    }
+    for &ch in &chars[j..i] {
+        match ch {
+            '\\' | '[' | ']' | '^' | '&' | '~' => regex.push_str(&format!("\\{}", ch)),
+            ch => regex.push(ch),
+        }
+    }
+    regex.push(']');
+    i + 1
+}
+
+impl Glob {
+    /// Returns the error if the pattern cannot be matched, e.g. the character class `[z-a]`
+    pub fn new(pattern: &str) -> Result<Glob, String> {
+        let mut body = pattern.trim_end();
+        let negated = body.starts_with('!');
+        if negated {
+            body = &body[1..];
+        }
+        let dir_only = body.ends_with('/');
+        if dir_only {
+            body = body.trim_end_matches('/');
+        }
+        let anchored = body.contains('/');
+        let body = body.trim_start_matches('/');
+
+        let mut regex = String::from("^");
+        if !anchored {
+            regex.push_str("(?:.*/)?");
+        }
+
+        let chars: Vec<char> = body.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    let at_start = i == 0 || chars[i - 1] == '/';
+                    match chars.get(i + 2) {
+                        Some('/') if at_start => {
+                            regex.push_str("(?:.*/)?");
+                            i += 3;
+                        }
+                        None if at_start => {
+                            regex.push_str(".*");
+                            i += 2;
+                        }
+                        _ => {
+                            regex.push_str("[^/]*");
+                            i += 2;
+                        }
+                    }
+                }
+                '*' => {
+                    regex.push_str("[^/]*");
+                    i += 1;
+                }
+                '?' => {
+                    regex.push_str("[^/]");
+                    i += 1;
+                }
+                '[' => i += push_class(&mut regex, &chars[i..]),
+                '\\' if i + 1 < chars.len() => {
+                    regex.push_str(&regex::escape(&chars[i + 1].to_string()));
+                    i += 2;
+                }
+                ch => {
+                    regex.push_str(&regex::escape(&ch.to_string()));
+                    i += 1;
+                }
+            }
+        }
+        regex.push('$');
+
+        match Regex::new(&regex) {
+            Ok(regex) => Ok(Glob { pattern: pattern.to_string(), regex, negated, dir_only }),
+            Err(_) => Err(format!("Invalid glob pattern `{}`", pattern)),
+        }
+    }
+
+    /// Returns the patterns of a `.gitignore`-like file, the empty lines and comments are skipped, `\#` and `\!` escape
+    /// the first character. The invalid patterns are returned as errors with their line numbers.
+    pub fn parse_list(content: &str) -> Vec<Result<Glob, String>> {
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|(i, line)| Glob::new(line).map_err(|err| format!("{} at line {}", err, i + 1)))
+            .collect()
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    /// Tests the path relative to the base directory, the components are separated by `/`
+    pub fn matches(&self, rel_path: &str, is_dir: bool) -> bool {
+        (is_dir || !self.dir_only) && self.regex.is_match(rel_path)
+    }
+
+    /// Tests the path of the file and the paths of the directories it is placed in
+    pub fn matches_file(&self, rel_path: &str) -> bool {
+        self.matches(rel_path, false)
+            || rel_path.match_indices('/').any(|(pos, _)| self.matches(&rel_path[..pos], true))
+    }
+}
+
+// the regex is derived from the pattern
+impl PartialEq for Glob {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+#[test]
+fn test_glob() {
+    let glob = Glob::new("*.lua").unwrap();
+    assert!(glob.matches("a.lua", false));
+    assert!(glob.matches("dir/sub/a.lua", false));
+    assert!(!glob.matches("a.luac", false));
+
+    let glob = Glob::new("/vendor/").unwrap();
+    assert!(glob.matches("vendor", true));
+    assert!(!glob.matches("vendor", false));
+    assert!(!glob.matches("src/vendor", true));
+
+    let glob = Glob::new("build").unwrap();
+    assert!(glob.matches("build", true));
+    assert!(glob.matches("src/build", false));
+
+    let glob = Glob::new("src/*.lua").unwrap();
+    assert!(glob.matches("src/a.lua", false));
+    assert!(!glob.matches("src/dir/a.lua", false));
+    assert!(!glob.matches("lib/src/a.lua", false));
+
+    let glob = Glob::new("**/gen/**").unwrap();
+    assert!(glob.matches("gen/a.lua", false));
+    assert!(glob.matches("src/gen/dir/a.lua", false));
+    assert!(!glob.matches("src/generated/a.lua", false));
+
+    let glob = Glob::new("spec/").unwrap();
+    assert!(glob.matches_file("spec/a.lua"));
+    assert!(glob.matches_file("src/spec/dir/a.lua"));
+    assert!(!glob.matches_file("spec.lua"));
+
+    let glob = Glob::new("a/**/b").unwrap();
+    assert!(glob.matches("a/b", true));
+    assert!(glob.matches("a/x/y/b", true));
+
+    let glob = Glob::new("file[0-9!].?ua").unwrap();
+    assert!(glob.matches("file1.lua", false));
+    assert!(glob.matches("file!.lua", false));
+    assert!(!glob.matches("filex.lua", false));
+    assert!(Glob::new("file[!0-9].lua").unwrap().matches("filex.lua", false));
+    assert!(Glob::new("a[b.lua").unwrap().matches("a[b.lua", false));
+    assert!(Glob::new("a\\*.lua").unwrap().matches("a*.lua", false));
+    assert!(!Glob::new("a\\*.lua").unwrap().matches("ab.lua", false));
+
+    assert_eq!(Glob::new("[z-a].lua"), Err("Invalid glob pattern `[z-a].lua`".to_string()));
+
+    let globs: Vec<Glob> = Glob::parse_list("# comment\n\n*.lua\n!keep.lua\n\\#name\n\\!name\n")
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let patterns: Vec<_> = globs.iter().map(|glob| (glob.pattern(), glob.is_negated())).collect();
+    assert_eq!(patterns, vec![("*.lua", false), ("!keep.lua", true), ("\\#name", false), ("\\!name", false)]);
+    assert!(globs[1].matches("keep.lua", false));
+    assert!(globs[2].matches("#name", false));
+    assert!(globs[3].matches("!name", false));
+
+    let globs = Glob::parse_list("*.lua\n[z-a]\nvendor/\n");
+    assert_eq!(globs.len(), 3);
+    assert_eq!(globs[1], Err("Invalid glob pattern `[z-a]` at line 2".to_string()));
+}
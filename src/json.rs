@@ -0,0 +1,352 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// the maximum nesting of the arrays and the objects, the values are parsed recursively
+const MAX_DEPTH: usize = 128;
+
+/// JSON value, the object keys keep their order
+#[derive(Debug, PartialEq, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn parse(src: &str) -> Result<Json, String> {
+        let mut chars = JChars { chars: src.chars().peekable(), line: 1, depth: 0 };
+        let value = parse_value(&mut chars).and_then(|value| parse_end(&mut chars, value));
+        value.map_err(|err| format!("{} at line {}", err, chars.line))
+    }
+
+    /// Parses the object, returns its members with the numbers of lines where they start
+    pub fn parse_object_members(src: &str) -> Result<Vec<(String, Json, usize)>, String> {
+        let mut chars = JChars { chars: src.chars().peekable(), line: 1, depth: 0 };
+        skip_whitespaces(&mut chars);
+        let members = match chars.peek() {
+            Some('{') => parse_members(&mut chars).and_then(|members| parse_end(&mut chars, members)),
+            _ => Err("expected an object".to_string()),
+        };
+        members.map_err(|err| format!("{} at line {}", err, chars.line))
+    }
+}
+
+// the chars of the source with the number of the current line and the nesting depth of the current value
+struct JChars<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    depth: usize,
+}
+
+impl JChars<'_> {
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+}
+
+impl Iterator for JChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.chars.next();
+        if ch == Some('\n') {
+            self.line += 1;
+        }
+        ch
+    }
+}
+
+fn parse_end<T>(chars: &mut JChars, value: T) -> Result<T, String> {
+    skip_whitespaces(chars);
+    match chars.next() {
+        None => Ok(value),
+        Some(ch) => Err(format!("unexpected character `{}` after the value", ch)),
+    }
+}
+
+fn skip_whitespaces(chars: &mut JChars) {
+    while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = chars.peek() {
+        chars.next();
+    }
+}
+
+fn expect_word(chars: &mut JChars, word: &str, value: Json) -> Result<Json, String> {
+    for expected in word.chars() {
+        if chars.next() != Some(expected) {
+            return Err(format!("invalid literal, expected `{}`", word));
+        }
+    }
+    Ok(value)
+}
+
+fn parse_value(chars: &mut JChars) -> Result<Json, String> {
+    skip_whitespaces(chars);
+    match chars.peek() {
+        Some('n') => expect_word(chars, "null", Json::Null),
+        Some('t') => expect_word(chars, "true", Json::Bool(true)),
+        Some('f') => expect_word(chars, "false", Json::Bool(false)),
+        Some('"') => Ok(Json::String(parse_string(chars)?)),
+        Some('[') => parse_nested(chars, parse_array),
+        Some('{') => parse_nested(chars, parse_object),
+        Some(&ch) if ch == '-' || ch.is_ascii_digit() => parse_number(chars),
+        Some(&ch) => Err(format!("unexpected character `{}`", ch)),
+        None => Err("unexpected end of input".to_string()),
+    }
+}
+
+fn parse_nested(chars: &mut JChars, parse: fn(&mut JChars) -> Result<Json, String>) -> Result<Json, String> {
+    if chars.depth == MAX_DEPTH {
+        return Err(format!("the nesting is deeper than {} levels", MAX_DEPTH));
+    }
+
+    chars.depth += 1;
+    let value = parse(chars);
+    chars.depth -= 1;
+    value
+}
+
+fn parse_number(chars: &mut JChars) -> Result<Json, String> {
+    let mut number = String::new();
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            '0'..='9' | '-' | '+' | '.' | 'e' | 'E' => number.push(ch),
+            _ => break,
+        }
+        chars.next();
+    }
+
+    match number.parse::<f64>() {
+        Ok(value) => Ok(Json::Number(value)),
+        _ => Err(format!("invalid number `{}`", number)),
+    }
+}
+
+fn parse_hex4(chars: &mut JChars) -> Result<u32, String> {
+    let hex: String = chars.take(4).collect();
+    match hex.len() == 4 {
+        true => u32::from_str_radix(&hex, 16).map_err(|_| format!("invalid escape sequence `\\u{}`", hex)),
+        false => Err("unexpected end of input".to_string()),
+    }
+}
+
+fn parse_string(chars: &mut JChars) -> Result<String, String> {
+    chars.next();
+
+    let mut result = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(result),
+            Some('\\') => match chars.next() {
+                Some('"') => result.push('"'),
+                Some('\\') => result.push('\\'),
+                Some('/') => result.push('/'),
+                Some('b') => result.push('\u{8}'),
+                Some('f') => result.push('\u{c}'),
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some('t') => result.push('\t'),
+                Some('u') => {
+                    let mut code = parse_hex4(chars)?;
+                    // characters outside of the basic plane are encoded with surrogate pairs
+                    if (0xD800..0xDC00).contains(&code) {
+                        if chars.next() != Some('\\') || chars.next() != Some('u') {
+                            return Err("invalid surrogate pair".to_string());
+                        }
+                        let low = parse_hex4(chars)?;
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                    }
+                    result.push(std::char::from_u32(code).unwrap_or('\u{FFFD}'));
+                }
+                Some(ch) => return Err(format!("invalid escape sequence `\\{}`", ch)),
+                None => return Err("unexpected end of input".to_string()),
+            },
+            Some(ch) => result.push(ch),
+            None => return Err("unexpected end of input".to_string()),
+        }
+    }
+}
+
+fn parse_array(chars: &mut JChars) -> Result<Json, String> {
+    chars.next();
+
+    let mut items = Vec::new();
+    skip_whitespaces(chars);
+    if chars.peek() == Some(&']') {
+        chars.next();
+        return Ok(Json::Array(items));
+    }
+
+    loop {
+        items.push(parse_value(chars)?);
+        skip_whitespaces(chars);
+        match chars.next() {
+            Some(',') => continue,
+            Some(']') => return Ok(Json::Array(items)),
+            _ => return Err("expected `,` or `]`".to_string()),
+        }
+    }
+}
+
+fn parse_object(chars: &mut JChars) -> Result<Json, String> {
+    let members = parse_members(chars)?;
+    Ok(Json::Object(members.into_iter().map(|(key, value, _)| (key, value)).collect()))
+}
+
+fn parse_members(chars: &mut JChars) -> Result<Vec<(String, Json, usize)>, String> {
+    chars.next();
+
+    let mut members = Vec::new();
+    skip_whitespaces(chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+        return Ok(members);
+    }
+
+    loop {
+        skip_whitespaces(chars);
+        if chars.peek() != Some(&'"') {
+            return Err("expected a member name".to_string());
+        }
+        let line = chars.line;
+        let key = parse_string(chars)?;
+
+        skip_whitespaces(chars);
+        if chars.next() != Some(':') {
+            return Err("expected `:`".to_string());
+        }
+        members.push((key, parse_value(chars)?, line));
+
+        skip_whitespaces(chars);
+        match chars.next() {
+            Some(',') => continue,
+            Some('}') => return Ok(members),
+            _ => return Err("expected `,` or `}`".to_string()),
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for ch in s.chars() {
+        match ch {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            ch if (ch as u32) < 0x20 => write!(f, "\\u{:04x}", ch as u32)?,
+            ch => write!(f, "{}", ch)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_string(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[test]
+fn test_json() {
+    let value = Json::parse(r#" {"id": 1, "params": {"text": "a\n\"b\" é😀", "list": [true, null, -1.5e1]}} "#);
+    let value = value.unwrap();
+    assert_eq!(value.get("id").and_then(Json::as_usize), Some(1));
+
+    let params = value.get("params").unwrap();
+    assert_eq!(params.get("text").and_then(Json::as_str), Some("a\n\"b\" é😀"));
+    assert_eq!(
+        params.get("list").and_then(Json::as_array),
+        Some(&vec![Json::Bool(true), Json::Null, Json::Number(-15.0)])
+    );
+    assert_eq!(params.get("missing"), None);
+
+    assert_eq!(value.to_string(), r#"{"id":1,"params":{"text":"a\n\"b\" é😀","list":[true,null,-15]}}"#);
+    assert_eq!(Json::parse(&value.to_string()), Ok(value));
+
+    assert_eq!(Json::parse(r#""\u0439\ud83d\ude00\t""#), Ok(Json::String("й😀\t".to_string())));
+    assert_eq!(Json::String("\u{1}".to_string()).to_string(), r#""\u0001""#);
+
+    assert_eq!(Json::parse("[]"), Ok(Json::Array(vec![])));
+    assert_eq!(Json::parse("{}"), Ok(Json::Object(vec![])));
+    assert_eq!(Json::parse("0.25").unwrap().to_string(), "0.25");
+    assert!(Json::parse("{\"a\" 1}").is_err());
+    assert!(Json::parse("[1, 2").is_err());
+    assert!(Json::parse("tru").is_err());
+    assert!(Json::parse("1 2").is_err());
+    assert_eq!(Json::parse("{\n\"a\": 1,\n\"b\" 2}"), Err("expected `:` at line 3".to_string()));
+
+    let members = Json::parse_object_members("{\n  \"a\": [1,\n 2],\n  \"b\": \"\"\n}\n");
+    assert_eq!(
+        members,
+        Ok(vec![
+            ("a".to_string(), Json::Array(vec![Json::Number(1.0), Json::Number(2.0)]), 2),
+            ("b".to_string(), Json::String(String::new()), 4),
+        ])
+    );
+    assert!(Json::parse_object_members("[]").is_err());
+
+    let nested = format!("{}{}", "[".repeat(128), "]".repeat(128));
+    assert!(Json::parse(&nested).is_ok());
+    let nested = format!("{}{}", "[{\"a\":".repeat(100_000), "1}]".repeat(100_000));
+    assert_eq!(Json::parse(&nested), Err("the nesting is deeper than 128 levels at line 1".to_string()));
+}
@@ -1,8 +1,19 @@
+pub mod ast;
 pub mod config;
+pub mod diff;
 pub mod file_util;
 pub mod formatter;
+pub mod glob;
+// the JSON parser of the configuration files and the language server, not a part of the public API
+#[doc(hidden)]
+pub mod json;
+pub mod markdown;
+pub mod style;
 
 mod formatting;
 mod parser;
+mod toml;
+
+pub use parser::{Dialect, SyntaxError};
 
 pub static CFG_PREFIX: &str = ".luafmt";
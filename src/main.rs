@@ -1,15 +1,28 @@
-use regex::Regex;
 use std::env;
 use std::fs;
 use std::io::{self, Read};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
 
-use luafmt::config::Config;
-use luafmt::file_util;
+use luafmt::config::{self, Config, OptionValue};
+use luafmt::diff;
+use luafmt::file_util::{self, FileFilter};
 use luafmt::formatter;
+use luafmt::glob::Glob;
 
 fn get_options_and_filenames() -> (Vec<String>, Vec<String>) {
-    let args: Vec<String> = env::args().skip(1).collect();
+    let mut args: Vec<String> = Vec::new();
+    let mut env_args = env::args().skip(1);
+    while let Some(arg) = env_args.next() {
+        // the values of the options may be passed as the next argument
+        match find_program_option(&arg, true) {
+            Some(_) => args.push(format!("{}={}", arg, env_args.next().unwrap_or_default())),
+            None => args.push(arg),
+        }
+    }
+
     let (options, mut sources): (Vec<_>, Vec<_>) = args.into_iter().partition(|arg| arg.starts_with('-'));
     sources.sort();
 
@@ -21,53 +34,342 @@ pub struct ProgramOpts {
     pub inplace: bool,
     pub recursive: bool,
     pub verbose: bool,
+    pub check: bool,
+    pub diff: bool,
+    pub jobs: usize,
+    pub exclude: Vec<String>,
+    pub include: Vec<String>,
+    // the extensions of the files taken from the directories, `DEFAULT_EXT` if empty
+    pub exts: Vec<String>,
+    pub print_config: Option<String>,
+    pub init: bool,
+    pub help: bool,
+    pub markdown: bool,
 }
 
 impl ProgramOpts {
     pub const fn default() -> Self {
-        ProgramOpts { inplace: false, recursive: false, verbose: false }
+        ProgramOpts {
+            inplace: false,
+            recursive: false,
+            verbose: false,
+            check: false,
+            diff: false,
+            jobs: 1,
+            exclude: Vec::new(),
+            include: Vec::new(),
+            exts: Vec::new(),
+            print_config: None,
+            init: false,
+            help: false,
+            markdown: false,
+        }
     }
 }
 
-fn parse_options(options: &Vec<String>) -> (Config, ProgramOpts) {
+#[derive(Debug, PartialEq)]
+enum ProcessResult {
+    Unchanged,
+    Changed,
+    Failed,
+}
+
+// exit codes of the `--check` mode
+const EXIT_UNFORMATTED: i32 = 1;
+const EXIT_FAILED: i32 = 2;
+
+// number of unchanged lines around every hunk of the `--diff` mode
+const DIFF_CONTEXT: usize = 3;
+
+// the extension of the files taken from the directories if `--ext` is not passed
+const DEFAULT_EXT: &str = "lua";
+
+// the file which is written to the current directory by `--init`
+const INIT_CONFIG_FILE: &str = ".luafmt.lua";
+
+// The program options: the names without the dashes, the description and the action. The options may be passed with
+// one or two dashes, the values are passed after `=` or as the next argument.
+struct ProgramOption {
+    names: &'static [&'static str],
+    description: &'static str,
+    action: ProgramAction,
+}
+
+enum ProgramAction {
+    Flag(fn(&mut Config, &mut ProgramOpts)),
+    // the placeholder of the value in the help and the function which applies the value
+    Value(&'static str, fn(&mut Config, &mut ProgramOpts, &str) -> Result<(), String>),
+}
+
+use ProgramAction::{Flag, Value};
+
+static PROGRAM_OPTIONS: &[ProgramOption] = &[
+    ProgramOption {
+        names: &["i", "inplace"],
+        description: "update the files in place",
+        action: Flag(|_, program_opts| program_opts.inplace = true),
+    },
+    ProgramOption {
+        names: &["r", "recursive"],
+        description: "process the files of the subdirectories",
+        action: Flag(|_, program_opts| program_opts.recursive = true),
+    },
+    ProgramOption {
+        names: &["c", "check"],
+        description: "list the files which would be reformatted",
+        action: Flag(|_, program_opts| program_opts.check = true),
+    },
+    ProgramOption {
+        names: &["d", "diff"],
+        description: "print the changes as a unified diff",
+        action: Flag(|_, program_opts| program_opts.diff = true),
+    },
+    ProgramOption {
+        names: &["v", "verbose"],
+        description: "print the options and the processed files, the files are formatted in a single thread",
+        action: Flag(|_, program_opts| program_opts.verbose = true),
+    },
+    ProgramOption {
+        names: &["j", "jobs"],
+        description: "format N files concurrently, `-v` formats one file at a time",
+        action: Value("N", |_, program_opts, value| match value.parse::<usize>() {
+            Ok(jobs) if jobs > 0 => {
+                program_opts.jobs = jobs;
+                Ok(())
+            }
+            _ => Err(format!("Invalid number of jobs `{}`", value)),
+        }),
+    },
+    ProgramOption {
+        names: &["style"],
+        description: "use the built-in style for the format options which are not set",
+        action: Value("NAME", |config, _, value| config.try_set("style", &OptionValue::Text(value.to_string()))),
+    },
+    ProgramOption {
+        names: &["exclude"],
+        description: "skip the matching files of the directories",
+        action: Value("GLOB", |_, program_opts, value| {
+            program_opts.exclude.push(value.to_string());
+            Ok(())
+        }),
+    },
+    ProgramOption {
+        names: &["include"],
+        description: "format only the matching files of the directories",
+        action: Value("GLOB", |_, program_opts, value| {
+            program_opts.include.push(value.to_string());
+            Ok(())
+        }),
+    },
+    ProgramOption {
+        names: &["ext"],
+        description: "format the files of the directories with these extensions instead of `lua` and of the \
+                      extensionless files with a lua shebang (e.g. `#!/usr/bin/env lua`)",
+        action: Value("EXT,...", |_, program_opts, value| {
+            let exts = value.split(',').map(|ext| ext.trim().trim_start_matches('.'));
+            program_opts.exts.extend(exts.filter(|ext| !ext.is_empty()).map(str::to_string));
+            Ok(())
+        }),
+    },
+    ProgramOption {
+        names: &["markdown"],
+        description: "format the lua code blocks of the markdown files of the directories (or of stdin)",
+        action: Flag(|_, program_opts| program_opts.markdown = true),
+    },
+    ProgramOption {
+        names: &["verify"],
+        description: "report the formatter bugs instead of writing the broken output",
+        action: Flag(|config, _| config.verify = Some(true)),
+    },
+    ProgramOption {
+        names: &["print-config"],
+        description: "print the options which would be used to format the file",
+        action: Value("FILE", |_, program_opts, value| {
+            program_opts.print_config = Some(value.to_string());
+            Ok(())
+        }),
+    },
+    ProgramOption {
+        names: &["init"],
+        description: "write `.luafmt.lua` with all format options into the current directory",
+        action: Flag(|_, program_opts| program_opts.init = true),
+    },
+    ProgramOption {
+        names: &["h", "help"],
+        description: "print this help",
+        action: Flag(|_, program_opts| program_opts.help = true),
+    },
+];
+
+// returns the program option of the argument without the value, `with_value` selects the options which take a value
+fn find_program_option(arg: &str, with_value: bool) -> Option<&'static ProgramOption> {
+    let name = arg.strip_prefix("--").or_else(|| arg.strip_prefix('-'))?;
+    PROGRAM_OPTIONS
+        .iter()
+        .find(|option| option.names.contains(&name) && matches!(option.action, Value(..)) == with_value)
+}
+
+fn parse_options(options: &[String]) -> (Config, ProgramOpts) {
     let mut config = Config::default();
     let mut program_opts = ProgramOpts::default();
 
     for option in options.iter() {
-        let re_config_opt = Regex::new(r"^--([a-zA-Z_0-9]+)=(.*)$").unwrap();
-        let re_program_opt = Regex::new(r"^[-]{1,2}([a-z]+)$").unwrap();
-
-        match re_config_opt.captures_iter(option).next() {
-            Some(cap) => config.set(&cap[1], &cap[2]),
-            None => match re_program_opt.captures_iter(option).next() {
-                Some(cap) if &cap[1] == "i" || &cap[1] == "inplace" => program_opts.inplace = true,
-                Some(cap) if &cap[1] == "r" || &cap[1] == "recursive" => program_opts.recursive = true,
-                Some(cap) if &cap[1] == "v" || &cap[1] == "verbose" => program_opts.verbose = true,
-                _ => eprintln!("Unrecognized option `{}`", option),
-            },
+        let (arg, value) = match option.split_once('=') {
+            Some((arg, value)) => (arg, Some(value)),
+            None => (option.as_str(), None),
+        };
+
+        // the other `--NAME=VALUE` options are the configuration options
+        let result = match (find_program_option(arg, value.is_some()).map(|option| &option.action), value) {
+            (Some(Flag(apply)), _) => {
+                apply(&mut config, &mut program_opts);
+                Ok(())
+            }
+            (Some(Value(_, apply)), value) => apply(&mut config, &mut program_opts, value.unwrap_or_default()),
+            (None, Some(value)) if arg.starts_with("--") => {
+                config.try_set(&arg[2..], &OptionValue::Text(value.to_string()))
+            }
+            (None, _) => Err(format!("Unrecognized option `{}`", option)),
         };
+        if let Err(err) = result {
+            eprintln!("{}", err);
+        }
     }
 
     (config, program_opts)
 }
 
-fn process_file_path(file_path: &PathBuf, config: &Config, program_opts: &ProgramOpts) {
-    match formatter::process_file(&file_path, &config, program_opts.verbose) {
-        Ok(output) => match program_opts.inplace {
-            true => match fs::write(file_path, output) {
-                Ok(..) => {}
-                Err(err) => {
-                    eprintln!("{}", format!("An error occured while writing file `{}`: {}", file_path.display(), err))
+fn help_text() -> String {
+    let mut text = String::from("Usage: luafmt [options] [configuration options] {sources/directories}\n");
+    text.push_str("The standard input is formatted if there are no sources.\n\nOptions:\n");
+    for option in PROGRAM_OPTIONS.iter() {
+        let dashes = |name: &str| if name.len() == 1 { "-" } else { "--" };
+        let names: Vec<_> = option.names.iter().map(|name| format!("{}{}", dashes(name), name)).collect();
+        let flags = match option.action {
+            Value(placeholder, _) => format!("{} {}", names.join(", "), placeholder),
+            Flag(_) => names.join(", "),
+        };
+        text.push_str(&format!("  {:<22}{}\n", flags, option.description));
+    }
+
+    text.push_str("\nConfiguration options (--NAME=VALUE):\n");
+    let mut category = None;
+    for option in config::all_options() {
+        if category != Some(option.category) {
+            text.push_str(&format!("\n {}:\n", option.category.title()));
+            category = Some(option.category);
+        }
+        let values = option.domain.describe(option.type_name);
+        text.push_str(&format!("  --{} ({})\n      {}\n", option.name, values, option.description));
+    }
+    text
+}
+
+fn process_output(
+    source: &str, output: String, file_path: Option<&PathBuf>, program_opts: &ProgramOpts,
+) -> ProcessResult {
+    let result = match source == output {
+        true => ProcessResult::Unchanged,
+        false => ProcessResult::Changed,
+    };
+
+    if program_opts.check || program_opts.diff {
+        if result == ProcessResult::Changed {
+            let name = match file_path {
+                Some(file_path) => file_path.display().to_string(),
+                None => "<stdin>".to_string(),
+            };
+
+            match program_opts.diff {
+                true => print!("{}", diff::unified_diff(source, &output, &name, &name, DIFF_CONTEXT)),
+                false => println!("{}", name),
+            }
+        }
+        return result;
+    }
+
+    match (program_opts.inplace, file_path) {
+        (true, Some(file_path)) => {
+            if result == ProcessResult::Changed {
+                if let Err(err) = fs::write(file_path, output) {
+                    eprintln!("An error occured while writing file `{}`: {}", file_path.display(), err);
+                    return ProcessResult::Failed;
                 }
-            },
-            false => print!("{}", output),
-        },
+            }
+        }
+        _ => print!("{}", output),
+    }
+
+    result
+}
+
+// the source and the formatted output of a file
+type FormattedFile = Result<(String, String), formatter::FormatterError>;
+
+fn process_file_path(file_path: &PathBuf, formatted: FormattedFile, program_opts: &ProgramOpts) -> ProcessResult {
+    match formatted {
+        Ok((source, output)) => process_output(&source, output, Some(file_path), program_opts),
         Err(err) => {
-            eprintln!("{}", format!("An error occured while processing file `{}`: {}", file_path.display(), err))
+            eprintln!("An error occured while processing file `{}`: {}", file_path.display(), err);
+            ProcessResult::Failed
         }
     }
 }
 
+// The files are formatted by `program_opts.jobs` worker threads. The results are printed (or written) by the main
+// thread in the order of `file_paths`, so the output does not depend on the number of jobs.
+fn process_file_paths(file_paths: &[PathBuf], config: &Config, program_opts: &ProgramOpts) -> Vec<ProcessResult> {
+    let jobs = program_opts.jobs.min(file_paths.len());
+
+    // verbose messages are printed while formatting, several threads would interleave them
+    if jobs <= 1 || program_opts.verbose {
+        return file_paths
+            .iter()
+            .map(|file_path| {
+                let formatted = formatter::process_file_with_source(file_path, config, program_opts.verbose);
+                process_file_path(file_path, formatted, program_opts)
+            })
+            .collect();
+    }
+
+    let next_index = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let next_index = &next_index;
+            scope.spawn(move || loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                match file_paths.get(index) {
+                    Some(file_path) => {
+                        let formatted = formatter::process_file_with_source(file_path, config, false);
+                        if sender.send((index, formatted)).is_err() {
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            });
+        }
+        drop(sender);
+
+        // the results which are received ahead of their turn wait for the previous ones
+        let mut pending: Vec<Option<FormattedFile>> = file_paths.iter().map(|_| None).collect();
+        let mut results = Vec::with_capacity(file_paths.len());
+
+        for (index, formatted) in receiver {
+            pending[index] = Some(formatted);
+            while let Some(formatted) = pending.get_mut(results.len()).and_then(Option::take) {
+                let file_path = &file_paths[results.len()];
+                results.push(process_file_path(file_path, formatted, program_opts));
+            }
+        }
+
+        results
+    })
+}
+
 fn main() {
     let (options, rel_paths) = get_options_and_filenames();
     let (config, program_opts) = parse_options(&options);
@@ -77,28 +379,105 @@ fn main() {
         println!("Program options: {:?}", program_opts);
     }
 
+    if program_opts.help {
+        print!("{}", help_text());
+        return;
+    }
+
+    if program_opts.init {
+        let file_path = Path::new(INIT_CONFIG_FILE);
+        if file_path.exists() {
+            eprintln!("The configuration file `{}` already exists", INIT_CONFIG_FILE);
+            std::process::exit(1);
+        }
+        if let Err(err) = fs::write(file_path, config::documented_config()) {
+            eprintln!("An error occured while writing file `{}`: {}", INIT_CONFIG_FILE, err);
+            std::process::exit(1);
+        }
+        println!("Created `{}`", INIT_CONFIG_FILE);
+        return;
+    }
+
+    if let Some(rel_path) = &program_opts.print_config {
+        match formatter::print_file_config(Path::new(rel_path), &config) {
+            Ok(content) => print!("{}", content),
+            Err(msg) => {
+                eprintln!("An error occured while resolving configuration of `{}`: {}", rel_path, msg);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let mut results = Vec::new();
+
     if rel_paths.is_empty() {
         let mut buffer = String::new();
         io::stdin().read_to_string(&mut buffer).unwrap();
 
-        match formatter::process_buffer_with_config(&buffer, &config, program_opts.verbose) {
-            Ok(output) => print!("{}", output),
-            Err(msg) => eprintln!("{}", format!("An error occured while processing buffer: {}", msg)),
+        let process = match program_opts.markdown {
+            true => formatter::process_markdown_with_config,
+            false => formatter::process_buffer_with_config,
+        };
+
+        match process(&buffer, &config, program_opts.verbose) {
+            Ok(output) => results.push(process_output(&buffer, output, None, &program_opts)),
+            Err(msg) => {
+                eprintln!("An error occured while processing buffer: {}", msg);
+                results.push(ProcessResult::Failed);
+            }
         }
     } else {
+        let parse_globs = |patterns: &[String]| -> Vec<Glob> {
+            match patterns.iter().map(|pattern| Glob::new(pattern)).collect() {
+                Ok(globs) => globs,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        };
+        let filter = FileFilter {
+            ignore_files: true,
+            excludes: parse_globs(&program_opts.exclude),
+            includes: parse_globs(&program_opts.include),
+            // the extensionless scripts are taken only with the default extensions
+            lua_shebang: !program_opts.markdown && program_opts.exts.is_empty(),
+        };
+
+        let exts: Vec<&str> = match (program_opts.markdown, program_opts.exts.is_empty()) {
+            (true, _) => vec![formatter::MARKDOWN_EXT],
+            (false, true) => vec![DEFAULT_EXT],
+            (false, false) => program_opts.exts.iter().map(String::as_str).collect(),
+        };
+
         for rel_path in &rel_paths {
             let path_buf = Path::new(rel_path).to_path_buf();
 
-            match file_util::get_path_files(&path_buf, program_opts.recursive, "lua", luafmt::CFG_PREFIX) {
-                Ok(file_paths) => {
-                    for file_path in &file_paths {
-                        process_file_path(file_path, &config, &program_opts);
-                    }
+            match file_util::get_filtered_path_files(
+                &path_buf,
+                program_opts.recursive,
+                &exts,
+                luafmt::CFG_PREFIX,
+                &filter,
+            ) {
+                Ok(file_paths) => results.extend(process_file_paths(&file_paths, &config, &program_opts)),
+                Err(_) => {
+                    eprintln!("Unresolved path: `{}`", rel_path);
+                    results.push(ProcessResult::Failed);
                 }
-                Err(_) => eprintln!("Unresolved path: `{}`", rel_path),
             }
         }
     }
+
+    if program_opts.check {
+        if results.contains(&ProcessResult::Failed) {
+            std::process::exit(EXIT_FAILED);
+        }
+        if results.contains(&ProcessResult::Changed) {
+            std::process::exit(EXIT_UNFORMATTED);
+        }
+    }
 }
 
 #[test]
@@ -139,4 +518,109 @@ fn test_parse_options() {
     let cfg = Config { ..Config::default() };
     let po = ProgramOpts::default();
     assert_eq!(parse_options(&options), (cfg, po));
+
+    let options = vec!["--check".to_string(), "-r".to_string()];
+    let cfg = Config { ..Config::default() };
+    let po = ProgramOpts { check: true, recursive: true, ..ProgramOpts::default() };
+    assert_eq!(parse_options(&options), (cfg, po));
+
+    let options = vec!["-c".to_string(), "--max_width=80".to_string()];
+    let cfg = Config { fmt: FormatOpts { max_width: Some(80), ..FormatOpts::default() }, ..Config::default() };
+    let po = ProgramOpts { check: true, ..ProgramOpts::default() };
+    assert_eq!(parse_options(&options), (cfg, po));
+
+    let options = vec!["--diff".to_string(), "-v".to_string()];
+    let cfg = Config { ..Config::default() };
+    let po = ProgramOpts { diff: true, verbose: true, ..ProgramOpts::default() };
+    assert_eq!(parse_options(&options), (cfg, po));
+
+    let options = vec!["--jobs=4".to_string(), "-r".to_string(), "--jobs=0".to_string()];
+    let cfg = Config { ..Config::default() };
+    let po = ProgramOpts { jobs: 4, recursive: true, ..ProgramOpts::default() };
+    assert_eq!(parse_options(&options), (cfg, po));
+
+    let options =
+        vec!["--exclude=vendor/".to_string(), "--include=src/**".to_string(), "--exclude=*_gen.lua".to_string()];
+    let cfg = Config { ..Config::default() };
+    let po = ProgramOpts {
+        exclude: vec!["vendor/".to_string(), "*_gen.lua".to_string()],
+        include: vec!["src/**".to_string()],
+        ..ProgramOpts::default()
+    };
+    assert_eq!(parse_options(&options), (cfg, po));
+
+    let options = vec!["-h".to_string(), "--init".to_string()];
+    let po = ProgramOpts { init: true, help: true, ..ProgramOpts::default() };
+    assert_eq!(parse_options(&options), (Config::default(), po));
+
+    let options = vec!["--print-config=src/file.lua".to_string(), "--max_width=80".to_string()];
+    let cfg = Config { fmt: FormatOpts { max_width: Some(80), ..FormatOpts::default() }, ..Config::default() };
+    let po = ProgramOpts { print_config: Some("src/file.lua".to_string()), ..ProgramOpts::default() };
+    assert_eq!(parse_options(&options), (cfg, po));
+
+    let options = vec!["--ext=lua, .luau,,p8".to_string(), "--ext=rockspec".to_string()];
+    let po = ProgramOpts {
+        exts: vec!["lua".to_string(), "luau".to_string(), "p8".to_string(), "rockspec".to_string()],
+        ..ProgramOpts::default()
+    };
+    assert_eq!(parse_options(&options), (Config::default(), po));
+
+    let options = vec!["--verify".to_string(), "-i".to_string(), "--markdown".to_string()];
+    let cfg = Config { verify: Some(true), ..Config::default() };
+    let po = ProgramOpts { inplace: true, markdown: true, ..ProgramOpts::default() };
+    assert_eq!(parse_options(&options), (cfg, po));
+
+    // the flags with a value are the configuration options
+    let options = vec!["--style=compact".to_string(), "--verify=false".to_string(), "-j=2".to_string()];
+    let cfg = Config { style: Some("compact".to_string()), verify: Some(false), ..Config::default() };
+    let po = ProgramOpts { jobs: 2, ..ProgramOpts::default() };
+    assert_eq!(parse_options(&options), (cfg, po));
+}
+
+#[test]
+fn test_help_text() {
+    let text = help_text();
+    for option in config::all_options() {
+        assert!(text.contains(&format!("--{} ", option.name)), "{}", option.name);
+    }
+    assert!(text.contains("  -j, --jobs N "));
+    assert!(text.contains("  --print-config FILE "));
+}
+
+#[test]
+fn test_process_file_paths() {
+    let config = Config { style: Some("default".to_string()), ..Config::default() };
+    let formatted = formatter::process_buffer_with_config(&"a  =  1\n".to_string(), &config, false).unwrap();
+
+    // the first file takes the longest, so the results of the next ones are received ahead of it
+    let sources: Vec<_> = (0..12)
+        .map(|i| ["b  =  2\n", formatted.as_str(), "local = 3\n"][i % 3].repeat(if i == 0 { 5_000 } else { i }))
+        .collect();
+    let expected: Vec<_> = (0..sources.len())
+        .map(|i| match i % 3 {
+            0 => ProcessResult::Changed,
+            1 => ProcessResult::Unchanged,
+            _ => ProcessResult::Failed,
+        })
+        .collect();
+
+    let root = env::temp_dir().join(format!("luafmt_jobs_{}", std::process::id()));
+    let process = |jobs: usize| {
+        let dir = root.join(jobs.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let file_paths: Vec<_> = (0..sources.len()).map(|i| dir.join(format!("file{}.lua", i))).collect();
+        for (file_path, source) in file_paths.iter().zip(&sources) {
+            fs::write(file_path, source).unwrap();
+        }
+
+        let program_opts = ProgramOpts { inplace: true, jobs, ..ProgramOpts::default() };
+        let results = process_file_paths(&file_paths, &config, &program_opts);
+        let outputs: Vec<_> = file_paths.iter().map(|file_path| fs::read_to_string(file_path).unwrap()).collect();
+        (results, outputs)
+    };
+
+    let (results, outputs) = process(4);
+    assert_eq!(results, expected);
+    assert_eq!(process(1), (results, outputs));
+    let _ = fs::remove_dir_all(&root);
 }
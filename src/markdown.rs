@@ -0,0 +1,122 @@
+// the opening fence of the code block
+struct Fence {
+    ch: char,
+    len: usize,
+    is_lua: bool,
+}
+
+fn parse_fence(line: &str) -> Option<Fence> {
+    let ch = line.chars().next().filter(|&ch| ch == '`' || ch == '~')?;
+    let len = line.chars().take_while(|&c| c == ch).count();
+    if len < 3 {
+        return None;
+    }
+
+    let info = line[len..].trim();
+    if ch == '`' && info.contains('`') {
+        return None;
+    }
+
+    let is_lua = info.split_whitespace().next().map(|lang| lang.eq_ignore_ascii_case("lua")) == Some(true);
+    Some(Fence { ch, len, is_lua })
+}
+
+fn is_closing_fence(line: &str, fence: &Fence) -> bool {
+    let len = line.chars().take_while(|&c| c == fence.ch).count();
+    len >= fence.len && line[len..].trim().is_empty()
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+// the lines which are indented less than the fence lose only their own indentation
+fn remove_indent<'a>(line: &'a str, indent: &str) -> &'a str {
+    match line.strip_prefix(indent) {
+        Some(line) => line,
+        None => line.trim_start_matches([' ', '\t']),
+    }
+}
+
+fn add_indent(code: &str, indent: &str) -> String {
+    let mut result = String::new();
+    for line in code.lines() {
+        if !line.is_empty() {
+            result.push_str(indent);
+            result.push_str(line);
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// Replaces the content of every fenced ```` ```lua ```` code block of the markdown document with the result of
+/// `format`. The code is passed without the indentation of the fence with the 0-based line number of its first line
+/// and the indentation, which is added back to the result. The unclosed blocks and the blocks of other languages are
+/// kept as is.
+pub fn format_lua_blocks<E>(
+    content: &str, mut format: impl FnMut(&str, usize, &str) -> Result<String, E>,
+) -> Result<String, E> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut result = String::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        result.push_str(line);
+        i += 1;
+
+        let indent = leading_whitespace(line);
+        let fence = match parse_fence(line[indent.len()..].trim_end()) {
+            Some(fence) => fence,
+            None => continue,
+        };
+
+        let first = i;
+        let closing = (first..lines.len()).find(|&j| is_closing_fence(lines[j].trim(), &fence));
+        let last = match closing {
+            Some(last) => last,
+            None => {
+                lines[first..].iter().for_each(|line| result.push_str(line));
+                break;
+            }
+        };
+
+        let code: String = lines[first..last].iter().map(|line| remove_indent(line, indent)).collect();
+        match fence.is_lua && !code.trim().is_empty() {
+            true => result.push_str(&add_indent(&format(&code, first, indent)?, indent)),
+            false => lines[first..last].iter().for_each(|line| result.push_str(line)),
+        }
+
+        result.push_str(lines[last]);
+        i = last + 1;
+    }
+
+    Ok(result)
+}
+
+#[test]
+fn test_format_lua_blocks() {
+    let upper = |code: &str, _: usize, _: &str| -> Result<String, ()> { Ok(code.to_uppercase()) };
+
+    let content = "# Title\n\n```lua\nlocal a = 1\n```\n\n```sh\n$ luafmt a.lua\n```\n";
+    assert_eq!(
+        format_lua_blocks(content, upper),
+        Ok("# Title\n\n```lua\nLOCAL A = 1\n```\n\n```sh\n$ luafmt a.lua\n```\n".to_string())
+    );
+
+    // the blocks of the list items are indented
+    let content = "* item\n\n  ~~~~ Lua\n  if a then\n\n    b()\n  end\n  ~~~~\n";
+    let mut blocks = Vec::new();
+    let actual = format_lua_blocks(content, |code, line, indent| -> Result<String, ()> {
+        blocks.push((code.to_string(), line, indent.to_string()));
+        Ok("if a then b() end".to_string())
+    });
+    assert_eq!(actual, Ok("* item\n\n  ~~~~ Lua\n  if a then b() end\n  ~~~~\n".to_string()));
+    assert_eq!(blocks, vec![("if a then\n\n  b()\nend\n".to_string(), 3, "  ".to_string())]);
+
+    // the fences inside of other blocks, the unclosed blocks and the errors
+    let content = "````md\n```lua\na\n```\n````\n```lua\nb\n";
+    assert_eq!(format_lua_blocks(content, upper), Ok(content.to_string()));
+    assert_eq!(format_lua_blocks("```lua\na\n```", |_, line, _| Err(line)), Err(1));
+}
@@ -19,6 +19,47 @@ pub enum Node {
     Chunk(Loc, Box<Node>, Loc),
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Directive {
+    Off,
+    On,
+    IgnoreNext,
+}
+
+impl Directive {
+    // recognizes `luafmt: off`, `luafmt: on` and `luafmt: ignore-next` comment texts
+    fn from_comment_text(text: &str) -> Option<Directive> {
+        let text = text.trim();
+        if !text.starts_with("luafmt:") {
+            return None;
+        }
+
+        match text["luafmt:".len()..].trim() {
+            "off" => Some(Directive::Off),
+            "on" => Some(Directive::On),
+            "ignore-next" => Some(Directive::IgnoreNext),
+            _ => None,
+        }
+    }
+}
+
+impl Node {
+    pub fn directives(&self) -> Vec<(Loc, Directive)> {
+        use Node::*;
+        match self {
+            OneLineComment(span, s) | MultiLineComment(span, _, s) => match Directive::from_comment_text(s) {
+                Some(directive) => vec![(span.clone(), directive)],
+                None => Vec::new(),
+            },
+            NewLine(..) => Vec::new(),
+            VariantList(_, items) | CommentList(_, items) | NewLineList(_, items) => {
+                items.iter().flat_map(|(_, node)| node.directives()).collect()
+            }
+            Chunk(_, n, _) => n.directives(),
+        }
+    }
+}
+
 impl list::ListOfItems<Node> for Node {
     fn items(&self) -> Option<&Vec<(Loc, Node)>> {
         use Node::*;
@@ -132,9 +173,12 @@ impl ConfiguredWrite for Node {
                         Some(prefix) => {
                             let strimmed = s.trim_start();
 
-                            // do not print the `prefix` if trimmed `s` is empty
+                            // do not print the `prefix` if trimmed `s` is empty, the Luau directives like `--!strict`
+                            // are kept as is
                             if strimmed.is_empty() {
                                 write!(f, "--\n")?;
+                            } else if s.starts_with('!') {
+                                write!(f, "--{}\n", s)?;
                             } else {
                                 write!(f, "--{}{}\n", prefix, strimmed)?;
                             }
@@ -85,3 +85,22 @@ fn test_comments() {
     assert_eq!(tsdef("\n\n--[[123]]--[[]]--\n\n\n--324"), Ok("\n\n--[[123]]--[[]]--\n\n\n--324\n".to_string()));
 }
 
+
+#[test]
+fn test_directives() {
+    use super::comment_ast::Directive;
+    use Directive::*;
+
+    let directives = |s: &str| -> Vec<(usize, usize, Directive)> {
+        match parse_comment(s) {
+            Ok(node) => node.directives().iter().map(|(loc, d)| (loc.0, loc.1, *d)).collect(),
+            Err(_) => Vec::new(),
+        }
+    };
+
+    assert_eq!(directives("-- luafmt: off\n"), vec![(0, 15, Off)]);
+    assert_eq!(directives("--luafmt:on"), vec![(0, 11, On)]);
+    assert_eq!(directives("  --[[ luafmt: ignore-next ]] --[=[luafmt: off]=]"), vec![(2, 29, IgnoreNext), (30, 49, Off)]);
+    assert_eq!(directives("\n-- a\n--  luafmt:   on  \n"), vec![(6, 25, On)]);
+    assert_eq!(directives("-- luafmt: of\n-- luafmt on\n-- luafmt: off, please\n-- xluafmt: off"), vec![]);
+}
@@ -2,6 +2,7 @@ use std::fmt::Write;
 
 use crate::config::*;
 
+/// The range of the source, the start and the end positions are counted in chars
 #[derive(Debug, Clone)]
 pub struct Loc(pub usize, pub usize);
 
@@ -29,6 +30,36 @@ impl Loc {
     }
 }
 
+// the optional parts of the nodes are written with the gaps before them
+impl<T: ConfiguredWrite> ConfiguredWrite for Option<T> {
+    fn configured_write(&self, f: &mut String, cfg: &Config, buf: &str, state: &mut State) -> std::fmt::Result {
+        match self {
+            Some(part) => part.configured_write(f, cfg, buf, state),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T1: ConfiguredWrite, T2: ConfiguredWrite> ConfiguredWrite for (T1, T2) {
+    fn configured_write(&self, f: &mut String, cfg: &Config, buf: &str, state: &mut State) -> std::fmt::Result {
+        self.0.configured_write(f, cfg, buf, state)?;
+        self.1.configured_write(f, cfg, buf, state)
+    }
+}
+
+impl<T: ConfiguredWrite + ?Sized> ConfiguredWrite for &T {
+    fn configured_write(&self, f: &mut String, cfg: &Config, buf: &str, state: &mut State) -> std::fmt::Result {
+        (**self).configured_write(f, cfg, buf, state)
+    }
+}
+
+impl<T: ConfiguredWrite + ?Sized> ConfiguredWrite for Box<T> {
+    fn configured_write(&self, f: &mut String, cfg: &Config, buf: &str, state: &mut State) -> std::fmt::Result {
+        (**self).configured_write(f, cfg, buf, state)
+    }
+}
+
+/// The text of the node which is not taken from the source, e.g. the operators
 #[derive(Debug)]
 pub struct Str<'a>(pub &'a str);
 
@@ -0,0 +1,132 @@
+use lalrpop_util::ParseError;
+
+use super::lua_lexer::{LexicalError, Token};
+
+type LuaParserError = ParseError<usize, Token, LexicalError>;
+
+/// The language of the sources, it changes the tokens of the lexer and the constructs of the grammar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dialect {
+    Lua51,
+    Lua52,
+    Lua53,
+    Lua54,
+    // Lua 5.1 with `goto` and the suffixes of the 64-bit integers and the imaginary numbers
+    LuaJIT,
+    // Roblox Luau: type annotations, compound assignments, `continue`, if-expressions and interpolated strings
+    Luau,
+}
+
+// the grammar terminals which are never produced by the lexer of the dialects without the feature
+static GOTO_TERMINALS: &[&str] = &["\"goto\"", "\"::\""];
+static BITWISE_TERMINALS: &[&str] = &["\"|\"", "\"&\"", "\"~\"", "\"<<\"", "\">>\""];
+static FLOOR_DIVISION_TERMINALS: &[&str] = &["\"//\""];
+static LUAU_TERMINALS: &[&str] = &[
+    "\"typeof\"",
+    "\"?\"",
+    "\"->\"",
+    "CompoundOp",
+    "TypeCast",
+    "TypeUnion",
+    "TypeIntersection",
+    "InterpolatedStringLiteral",
+];
+
+impl Dialect {
+    /// Returns the dialect of the `lua_version` option value
+    pub fn from_lua_version(version: &str) -> Option<Dialect> {
+        match version {
+            "5.1" => Some(Dialect::Lua51),
+            "5.2" => Some(Dialect::Lua52),
+            "5.3" => Some(Dialect::Lua53),
+            "5.4" => Some(Dialect::Lua54),
+            "luajit" => Some(Dialect::LuaJIT),
+            "luau" => Some(Dialect::Luau),
+            _ => None,
+        }
+    }
+
+    /// `goto` statements and `::labels::`
+    pub fn has_goto(self) -> bool {
+        matches!(self, Dialect::Lua52 | Dialect::Lua53 | Dialect::Lua54 | Dialect::LuaJIT)
+    }
+
+    /// `&`, `|`, `~`, `<<` and `>>` operators
+    pub fn has_bitwise_ops(self) -> bool {
+        matches!(self, Dialect::Lua53 | Dialect::Lua54)
+    }
+
+    /// `//` operator
+    pub fn has_floor_division(self) -> bool {
+        matches!(self, Dialect::Lua53 | Dialect::Lua54 | Dialect::Luau)
+    }
+
+    /// `<const>` and `<close>` attributes of the local variables
+    pub fn has_attribs(self) -> bool {
+        self == Dialect::Lua54
+    }
+
+    fn foreign_terminals(self) -> Vec<&'static str> {
+        let mut terminals = Vec::new();
+        if !self.has_goto() {
+            terminals.extend(GOTO_TERMINALS);
+        }
+        if !self.has_bitwise_ops() {
+            terminals.extend(BITWISE_TERMINALS);
+        }
+        if !self.has_floor_division() {
+            terminals.extend(FLOOR_DIVISION_TERMINALS);
+        }
+        if self != Dialect::Luau {
+            terminals.extend(LUAU_TERMINALS);
+        }
+        terminals
+    }
+
+    /// Removes the terminals of other dialects from the expected tokens of the error, the grammar is shared
+    pub(crate) fn filter_expected(self, err: LuaParserError) -> LuaParserError {
+        let foreign_terminals = self.foreign_terminals();
+        let filter = |expected: Vec<String>| -> Vec<String> {
+            // `if` starts the expressions only in Luau, the statement position expects `while` too
+            let foreign_if = self != Dialect::Luau && !expected.iter().any(|name| name == "\"while\"");
+            expected
+                .into_iter()
+                .filter(|name| !foreign_terminals.contains(&name.as_str()) && (!foreign_if || name != "\"if\""))
+                .collect()
+        };
+
+        match err {
+            ParseError::UnrecognizedEOF { location, expected } => {
+                ParseError::UnrecognizedEOF { location, expected: filter(expected) }
+            }
+            ParseError::UnrecognizedToken { token, expected } => {
+                ParseError::UnrecognizedToken { token, expected: filter(expected) }
+            }
+            err => err,
+        }
+    }
+}
+
+/// Fails on the first token of the construct if the dialect does not support it
+pub fn require_feature(supported: bool, token: (usize, Token, usize)) -> Result<(), LuaParserError> {
+    match supported {
+        true => Ok(()),
+        false => Err(ParseError::UnrecognizedToken { token, expected: Vec::new() }),
+    }
+}
+
+/// Fails on the first token of the Luau construct made of the tokens which other dialects have too
+pub fn require_luau(dialect: Dialect, token: (usize, Token, usize)) -> Result<(), LuaParserError> {
+    require_feature(dialect == Dialect::Luau, token)
+}
+
+/// Checks the contextual keyword of the Luau statement, e.g. `type` of the type declarations, which is a name for
+/// the lexer. The error points to the token following the word, as it does in other dialects.
+pub fn require_luau_keyword(
+    dialect: Dialect, word: &str, keyword: &str, next: (usize, Token, usize),
+) -> Result<(), LuaParserError> {
+    match word == keyword {
+        true => require_luau(dialect, next),
+        false => Err(ParseError::UnrecognizedToken { token: next, expected: Vec::new() }),
+    }
+}
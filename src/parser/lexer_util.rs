@@ -63,6 +63,25 @@ pub fn get_hex_integer_end(chars: &mut TChars, start: usize) -> (usize, bool, St
     }
 }
 
+// the suffixes of LuaJIT in any case: `LL` and `ULL` of the 64-bit integers, `i` of the imaginary numbers
+pub fn get_luajit_suffix_end(chars: &mut TChars, start: usize, is_integer: bool) -> (usize, String) {
+    let suffix: String =
+        chars.clone().map(|(_, ch)| ch).take_while(|ch| ch.is_ascii_alphanumeric() || *ch == '_').collect();
+    let valid = match &suffix.to_ascii_lowercase()[..] {
+        "i" => true,
+        "ll" | "ull" => is_integer,
+        _ => false,
+    };
+
+    match valid {
+        true => {
+            chars.nth(suffix.len() - 1);
+            (start + suffix.len(), suffix)
+        }
+        false => (start, String::new()),
+    }
+}
+
 pub fn get_float_end(chars: &mut TChars, start: usize) -> (usize, bool, String) {
     // we already got one float symbol
     let (end, _, mut result) = seek_end_by_predicate(chars, start, &|ch: char, _| !ch.is_ascii_digit() && ch != '.');
@@ -122,6 +141,36 @@ pub fn get_string_ends(chars: &mut TChars, prefix: char, start: usize) -> (usize
     }
 }
 
+pub fn get_interpolated_string_ends(chars: &mut TChars, start: usize) -> (usize, usize, bool, String) {
+    // we already got "`", the expressions in braces may have their own strings and braces
+    let mut result = String::new();
+    // the closing chars of the nested strings and braces
+    let mut closing = vec!['`'];
+    let mut escaped = false;
+    let mut end = start;
+
+    for (i, ch) in chars.by_ref() {
+        end = i + 1;
+        let last = *closing.last().unwrap();
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' if last != '}' => escaped = true,
+            _ if ch == last => {
+                closing.pop();
+                if closing.is_empty() {
+                    return (i, i + 1, true, result);
+                }
+            }
+            '{' if last == '`' || last == '}' => closing.push('}'),
+            '"' | '\'' | '`' if last == '}' => closing.push(ch),
+            _ => {}
+        }
+        result.push(ch);
+    }
+
+    (end, end, false, result)
+}
+
 fn get_oneline_comment_ends(chars: &mut TChars, start: usize) -> (usize, usize, bool, String) {
     // we already got "--" symbols
     let (text_end, succ, result) = seek_end_by_predicate(chars, start, &|ch: char, _| ch == '\n');
@@ -37,15 +37,29 @@ impl FieldsOpts {
     }
 }
 
+/// The optional child node with the gap before it
+pub type Part = Option<(Loc, Box<Node>)>;
+
+/// The node of the syntax tree. The first `Loc` of a node is its span (except the chunks), the other ones are the gaps
+/// between its tokens with the whitespace and the comments. The items of the lists are stored with the gap before the
+/// item, the separated lists also store the gap before the separator and the separator. The variants with `B` have a
+/// non-empty block, e.g. `WhileDoB` is `while e do block end`, the optional parts of the other nodes are `Part`s.
 #[derive(Debug)]
 pub enum Node {
+    /// `e1 op e2`, the operator is one of the binary operators of Lua or the type union `|` / intersection `&`
     BinaryOp(Loc, [Loc; 2], Str<'static>, Box<Node>, Box<Node>),
+    /// `op e` for `-`, `#` and `~`
     UnaryOp(Loc, [Loc; 1], Str<'static>, Box<Node>),
+    /// `not e`
     UnaryNot(Loc, [Loc; 1], Box<Node>),
 
+    /// The name or the expression in brackets followed by the suffixes, e.g. `a.b[c](d)`
     Var(Loc, [Loc; 1], Box<Node>, Box<Node>),
+    /// `(e)`
     RoundBrackets(Loc, [Loc; 2], Box<Node>),
+    /// `(args)` of a call
     ArgsRoundBrackets(Loc, [Loc; 2], Box<Node>),
+    /// `()` of a call
     ArgsRoundBracketsEmpty(Loc, [Loc; 1]),
 
     Nil(Loc),
@@ -53,83 +67,260 @@ pub enum Node {
     True(Loc),
     VarArg(Loc),
     Break(Loc),
+    Continue(Loc),
+    /// The number as it is written in the source
     Numeral(Loc, String),
+    /// `"s"`, the text is kept escaped
     NormalStringLiteral(Loc, String),
+    /// `'s'`, the text is kept escaped
     CharStringLiteral(Loc, String),
+    /// `[==[s]==]` with the number of `=`
     MultiLineStringLiteral(Loc, usize, String),
+    /// Luau `` `s {e}` ``, the text with the expressions
+    InterpolatedStringLiteral(Loc, String),
 
+    /// `{fields}`
     TableConstructor(Loc, [Loc; 2], Box<Node>, TableConstructorOpts),
+    /// `{}`
     TableConstructorEmpty(Loc, [Loc; 1]),
+    /// The fields of a table constructor with their separators
     Fields(Loc, Vec<(Loc, Node, Loc, String)>, FieldsOpts),
+    /// `[k] = v`
     FieldNamedBracket(Loc, [Loc; 4], Box<Node>, Box<Node>),
+    /// `name = v`
     FieldNamed(Loc, [Loc; 2], Box<Node>, Box<Node>),
+    /// `v`
     FieldSequential(Loc, Box<Node>),
 
+    /// `[e]` suffix
     TableIndex(Loc, [Loc; 2], Box<Node>),
+    /// `.name` suffix
     TableMember(Loc, [Loc; 1], Box<Node>),
+    /// `e1, e2`
     ExpList(Loc, Vec<(Loc, Node, Loc, String)>),
+    /// `n1, n2`
     NameList(Loc, Vec<(Loc, Node, Loc, String)>),
+    /// `name <attrib>` of Lua 5.4
+    AttName(Loc, [Loc; 3], Box<Node>, Box<Node>),
+    /// The parameters of a function
     ParList(Loc, Vec<(Loc, Node, Loc, String)>),
+    /// `v1, v2` of an assignment
     VarList(Loc, Vec<(Loc, Node, Loc, String)>),
+    /// `(e)` followed by the suffixes
     VarRoundSuffix(Loc, [Loc; 3], Box<Node>, Box<Node>),
+    /// The suffixes of a variable
     VarSuffixList(Loc, Vec<(Loc, Node)>),
+    /// `:name(args)` suffix
     FnMethodCall(Loc, [Loc; 2], Box<Node>, Box<Node>),
+    /// `function body` expression
     FunctionDef(Loc, [Loc; 1], Box<Node>),
-    FuncBody(Loc, [Loc; 2]),
-    FuncBodyB(Loc, [Loc; 3], Box<Node>),
-    FuncPBody(Loc, [Loc; 3], Box<Node>),
-    FuncPBodyB(Loc, [Loc; 4], Box<Node>, Box<Node>),
+    /// `(pars): T block end`, the parameters, the Luau return types and the block are optional. The locs are the gaps
+    /// before `)` and `end`, the optional parts keep the gaps before them (both gaps of `:` for the return types).
+    FuncBody(Loc, [Loc; 2], Part, Option<([Loc; 2], Box<Node>)>, Part),
+    /// Luau `<T>` followed by the function body
+    GenericFuncBody(Loc, [Loc; 1], Box<Node>, Box<Node>),
+    /// `a.b.c` of a function declaration
     FuncName(Loc, Vec<(Loc, Node, Loc, String)>),
+    /// `a.b:c` of a function declaration
     FuncNameSelf(Loc, [Loc; 2], Vec<(Loc, Node, Loc, String)>, Box<Node>),
+    /// `function name body`
     FuncDecl(Loc, [Loc; 2], Box<Node>, Box<Node>),
+    /// `local function name body`
     LocalFuncDecl(Loc, [Loc; 3], Box<Node>, Box<Node>),
 
+    /// The statements of a block
     StatementList(Loc, Vec<(Loc, Node)>),
+    /// `do end`
     DoEnd(Loc, [Loc; 1]),
+    /// `do block end`
     DoBEnd(Loc, [Loc; 2], Box<Node>),
+    /// `vars = exprs`
     VarsExprs(Loc, [Loc; 2], Box<Node>, Box<Node>),
+    /// Luau `var op= e`
+    CompoundAssignment(Loc, [Loc; 2], Str<'static>, Box<Node>, Box<Node>),
     Name(Loc, String),
+    /// `::name::`
     Label(Loc, [Loc; 2], Box<Node>),
+    /// `goto name`
     GoTo(Loc, [Loc; 1], Box<Node>),
+    /// `while e do end`
     WhileDo(Loc, [Loc; 3], Box<Node>),
+    /// `while e do block end`
     WhileDoB(Loc, [Loc; 4], Box<Node>, Box<Node>),
+    /// `repeat until e`
     RepeatUntil(Loc, [Loc; 2], Box<Node>),
+    /// `repeat block until e`
     RepeatBUntil(Loc, [Loc; 3], Box<Node>, Box<Node>),
-    ForInt(Loc, [Loc; 7], Box<Node>, Box<Node>, Box<Node>),
-    ForIntB(Loc, [Loc; 8], Box<Node>, Box<Node>, Box<Node>, Box<Node>),
-    ForIntStep(Loc, [Loc; 9], Box<Node>, Box<Node>, Box<Node>, Box<Node>),
-    ForIntStepB(Loc, [Loc; 10], Box<Node>, Box<Node>, Box<Node>, Box<Node>, Box<Node>),
-    ForRange(Loc, [Loc; 5], Box<Node>, Box<Node>),
-    ForRangeB(Loc, [Loc; 6], Box<Node>, Box<Node>, Box<Node>),
+    /// `for n = e1, e2, e3 do block end`, the step (with the gaps around its comma) and the block are optional
+    ForInt(Loc, [Loc; 7], Box<Node>, Box<Node>, Box<Node>, Option<([Loc; 2], Box<Node>)>, Part),
+    /// `for names in exprs do block end`, the block is optional
+    ForRange(Loc, [Loc; 5], Box<Node>, Box<Node>, Part),
 
+    /// `local names`
     LocalNames(Loc, [Loc; 1], Box<Node>),
+    /// `local names = exprs`
     LocalNamesExprs(Loc, [Loc; 3], Box<Node>, Box<Node>),
 
-    IfThen(Loc, [Loc; 3], Box<Node>),
-    IfThenB(Loc, [Loc; 4], Box<Node>, Box<Node>),
-    IfThenElse(Loc, [Loc; 4], Box<Node>),
-    IfThenBElse(Loc, [Loc; 5], Box<Node>, Box<Node>),
-    IfThenElseB(Loc, [Loc; 5], Box<Node>, Box<Node>),
-    IfThenBElseB(Loc, [Loc; 6], Box<Node>, Box<Node>, Box<Node>),
-    IfThenElseIf(Loc, [Loc; 4], Box<Node>, Box<Node>),
-    IfThenBElseIf(Loc, [Loc; 5], Box<Node>, Box<Node>, Box<Node>),
-    IfThenElseIfElse(Loc, [Loc; 5], Box<Node>, Box<Node>),
-    IfThenBElseIfElse(Loc, [Loc; 6], Box<Node>, Box<Node>, Box<Node>),
-    IfThenElseIfElseB(Loc, [Loc; 6], Box<Node>, Box<Node>, Box<Node>),
-    IfThenBElseIfElseB(Loc, [Loc; 7], Box<Node>, Box<Node>, Box<Node>, Box<Node>),
+    /// `if e then block elseif ... else block end`, the block, the `elseif` parts and the `else` part with its block are
+    /// optional. The locs are the gaps after `if`, before `then` and before `end`, the optional parts keep the gaps
+    /// before them.
+    IfThen(Loc, [Loc; 3], Box<Node>, Part, Part, Option<(Loc, Part)>),
+    /// The `elseif` parts of an `if` statement
     ElseIfThenVec(Loc, Vec<(Loc, Node)>),
-    ElseIfThen(Loc, [Loc; 2], Box<Node>),
-    ElseIfThenB(Loc, [Loc; 3], Box<Node>, Box<Node>),
-
+    /// `elseif e then block`, the block is optional
+    ElseIfThen(Loc, [Loc; 2], Box<Node>, Part),
+    /// Luau `if e1 then e2 elseif ... else e3` expression, the `elseif` parts are optional
+    IfThenElseExp(Loc, [Loc; 5], Box<Node>, Box<Node>, Part, Box<Node>),
+    /// The `elseif` parts of an `if` expression
+    ElseIfThenExpVec(Loc, Vec<(Loc, Node)>),
+    /// `elseif e1 then e2` of an `if` expression
+    ElseIfThenExp(Loc, [Loc; 3], Box<Node>, Box<Node>),
+
+    /// Luau `type Name = T`
+    TypeDecl(Loc, [Loc; 3], Box<Node>, Box<Node>),
+    /// Luau `export type Name = T`
+    ExportTypeDecl(Loc, [Loc; 4], Box<Node>, Box<Node>),
+    /// Luau `name: T`, also `[K]: V` of the table types
+    TypeAnnotation(Loc, [Loc; 2], Box<Node>, Box<Node>),
+    /// Luau `e :: T`
+    TypeCast(Loc, [Loc; 2], Box<Node>, Box<Node>),
+    /// Luau `T?`
+    TypeOptional(Loc, [Loc; 1], Box<Node>),
+    /// Luau `Name<T>`
+    TypeGeneric(Loc, [Loc; 1], Box<Node>, Box<Node>),
+    /// Luau `<T, U>`
+    Generics(Loc, [Loc; 2], Box<Node>),
+    /// The types separated by commas
+    TypeList(Loc, Vec<(Loc, Node, Loc, String)>),
+    /// Luau `(T, U)`
+    TypeRoundBrackets(Loc, [Loc; 2], Box<Node>),
+    /// Luau `()`
+    TypeRoundBracketsEmpty(Loc, [Loc; 1]),
+    /// Luau `(T) -> U`
+    TypeFunction(Loc, [Loc; 2], Box<Node>, Box<Node>),
+    /// Luau `...T`
+    TypeVarArg(Loc, [Loc; 1], Box<Node>),
+    /// Luau `T...`
+    TypePack(Loc, [Loc; 1], Box<Node>),
+    /// Luau `typeof(e)`
+    TypeOf(Loc, [Loc; 3], Box<Node>),
+
+    /// `return`
     RetStatNone(Loc),
+    /// `return exprs`
     RetStatExpr(Loc, [Loc; 1], Box<Node>),
+    /// `return;`
     RetStatNoneComma(Loc, [Loc; 1]),
+    /// `return exprs;`
     RetStatExprComma(Loc, [Loc; 2], Box<Node>),
+    /// The statements followed by the `return` statement
     StatsRetStat(Loc, [Loc; 1], Box<Node>, Box<Node>),
+    /// The whole source: the gap before the block, the block and the gap after it
     Chunk(Loc, Box<Node>, Loc),
+    /// The whole source starting with `#!`: the gaps are before the `#!` line, after it and after the block
     SheBangChunk(Loc, Box<Node>, Loc, Box<Node>, Loc),
+    /// The empty statement `;`
     Semicolon(Loc),
+    /// `#!` line
     SheBang(Loc, String),
+    /// The statement which failed to parse with `error_recovery`
+    Unparsed(Loc),
+}
+
+impl Node {
+    /// Returns the span of the node, the chunks return the gap before their block
+    pub fn loc(&self) -> &Loc {
+        use Node::*;
+        match self {
+            BinaryOp(loc, ..)
+            | UnaryOp(loc, ..)
+            | UnaryNot(loc, ..)
+            | Var(loc, ..)
+            | RoundBrackets(loc, ..)
+            | ArgsRoundBrackets(loc, ..)
+            | ArgsRoundBracketsEmpty(loc, ..)
+            | Nil(loc)
+            | False(loc)
+            | True(loc)
+            | VarArg(loc)
+            | Break(loc)
+            | Continue(loc)
+            | Numeral(loc, ..)
+            | NormalStringLiteral(loc, ..)
+            | CharStringLiteral(loc, ..)
+            | MultiLineStringLiteral(loc, ..)
+            | InterpolatedStringLiteral(loc, ..)
+            | TableConstructor(loc, ..)
+            | TableConstructorEmpty(loc, ..)
+            | Fields(loc, ..)
+            | FieldNamedBracket(loc, ..)
+            | FieldNamed(loc, ..)
+            | FieldSequential(loc, ..)
+            | TableIndex(loc, ..)
+            | TableMember(loc, ..)
+            | ExpList(loc, ..)
+            | NameList(loc, ..)
+            | AttName(loc, ..)
+            | ParList(loc, ..)
+            | VarList(loc, ..)
+            | VarRoundSuffix(loc, ..)
+            | VarSuffixList(loc, ..)
+            | FnMethodCall(loc, ..)
+            | FunctionDef(loc, ..)
+            | FuncBody(loc, ..)
+            | GenericFuncBody(loc, ..)
+            | FuncName(loc, ..)
+            | FuncNameSelf(loc, ..)
+            | FuncDecl(loc, ..)
+            | LocalFuncDecl(loc, ..)
+            | StatementList(loc, ..)
+            | DoEnd(loc, ..)
+            | DoBEnd(loc, ..)
+            | VarsExprs(loc, ..)
+            | CompoundAssignment(loc, ..)
+            | Name(loc, ..)
+            | Label(loc, ..)
+            | GoTo(loc, ..)
+            | WhileDo(loc, ..)
+            | WhileDoB(loc, ..)
+            | RepeatUntil(loc, ..)
+            | RepeatBUntil(loc, ..)
+            | ForInt(loc, ..)
+            | ForRange(loc, ..)
+            | LocalNames(loc, ..)
+            | LocalNamesExprs(loc, ..)
+            | IfThen(loc, ..)
+            | ElseIfThenVec(loc, ..)
+            | ElseIfThen(loc, ..)
+            | IfThenElseExp(loc, ..)
+            | ElseIfThenExpVec(loc, ..)
+            | ElseIfThenExp(loc, ..)
+            | TypeDecl(loc, ..)
+            | ExportTypeDecl(loc, ..)
+            | TypeAnnotation(loc, ..)
+            | TypeCast(loc, ..)
+            | TypeOptional(loc, ..)
+            | TypeGeneric(loc, ..)
+            | Generics(loc, ..)
+            | TypeList(loc, ..)
+            | TypeRoundBrackets(loc, ..)
+            | TypeRoundBracketsEmpty(loc, ..)
+            | TypeFunction(loc, ..)
+            | TypeVarArg(loc, ..)
+            | TypePack(loc, ..)
+            | TypeOf(loc, ..)
+            | RetStatNone(loc)
+            | RetStatExpr(loc, ..)
+            | RetStatNoneComma(loc, ..)
+            | RetStatExprComma(loc, ..)
+            | StatsRetStat(loc, ..)
+            | Chunk(loc, ..)
+            | SheBangChunk(loc, ..)
+            | Semicolon(loc)
+            | SheBang(loc, ..)
+            | Unparsed(loc) => loc,
+        }
+    }
 }
 
 impl<'a> list::AnyListItem<'a, Node> for Node {
@@ -264,7 +455,8 @@ impl list::SepListOfItems<Node> for Node {
             | VarList(_, items)
             | ParList(_, items)
             | FuncName(_, items)
-            | FuncNameSelf(_, _, items, _) => Some(items),
+            | FuncNameSelf(_, _, items, _)
+            | TypeList(_, items) => Some(items),
             _ => None,
         }
     }
@@ -272,7 +464,7 @@ impl list::SepListOfItems<Node> for Node {
     fn element_prefix_hint(&self) -> &str {
         use Node::*;
         match self {
-            Fields(..) | ExpList(..) | NameList(..) | VarList(..) | ParList(..) => " ",
+            Fields(..) | ExpList(..) | NameList(..) | VarList(..) | ParList(..) | TypeList(..) => " ",
             FuncName(..) | FuncNameSelf(..) => "",
             _ => "",
         }
@@ -282,7 +474,7 @@ impl list::SepListOfItems<Node> for Node {
         use Node::*;
         match self {
             Fields(..) => cfg.fmt.field_separator.clone(),
-            ExpList(..) | NameList(..) | VarList(..) | ParList(..) => Some(",".to_string()),
+            ExpList(..) | NameList(..) | VarList(..) | ParList(..) | TypeList(..) => Some(",".to_string()),
             FuncName(..) | FuncNameSelf(..) => Some(".".to_string()),
             _ => None,
         }
@@ -302,7 +494,9 @@ impl list::SepListOfItems<Node> for Node {
                     cfg.fmt.write_trailing_field_separator.clone()
                 }
             }
-            ExpList(..) | NameList(..) | VarList(..) | ParList(..) | FuncName(..) | FuncNameSelf(..) => Some(false),
+            ExpList(..) | NameList(..) | VarList(..) | ParList(..) | FuncName(..) | FuncNameSelf(..) | TypeList(..) => {
+                Some(false)
+            }
             _ => None,
         }
     }
@@ -321,7 +515,10 @@ impl list::ListOfItems<Node> for Node {
     fn items(&self) -> Option<&Vec<(Loc, Node)>> {
         use Node::*;
         match self {
-            StatementList(_, items) | VarSuffixList(_, items) | ElseIfThenVec(_, items) => Some(items),
+            StatementList(_, items)
+            | VarSuffixList(_, items)
+            | ElseIfThenVec(_, items)
+            | ElseIfThenExpVec(_, items) => Some(items),
             _ => None,
         }
     }
@@ -363,7 +560,10 @@ impl Node {
     fn test_oneline_table_field(&self, f: &mut String, cfg: &Config, buf: &str, state: &mut State) -> Option<String> {
         use Node::*;
         match self {
-            FieldNamedBracket(span, _, _, _) | FieldNamed(span, _, _, _) | FieldSequential(span, _)
+            FieldNamedBracket(span, _, _, _)
+            | FieldNamed(span, _, _, _)
+            | FieldSequential(span, _)
+            | TypeAnnotation(span, _, _, _)
                 if cfg.fmt.max_width.is_some()
                     && cfg.fmt.newline_format_table_field.is_some()
                     && span.1 - span.0 < 2 * cfg.fmt.max_width.unwrap_or(0) =>
@@ -377,18 +577,7 @@ impl Node {
     fn test_oneline_if(&self, f: &mut String, cfg: &Config, buf: &str, state: &mut State) -> Option<String> {
         use Node::*;
         match self {
-            IfThen(span, _, _)
-            | IfThenB(span, _, _, _)
-            | IfThenElse(span, _, _)
-            | IfThenBElse(span, _, _, _)
-            | IfThenElseB(span, _, _, _)
-            | IfThenBElseB(span, _, _, _, _)
-            | IfThenElseIf(span, _, _, _)
-            | IfThenBElseIf(span, _, _, _, _)
-            | IfThenElseIfElse(span, _, _, _)
-            | IfThenBElseIfElse(span, _, _, _, _)
-            | IfThenElseIfElseB(span, _, _, _, _)
-            | IfThenBElseIfElseB(span, _, _, _, _, _)
+            IfThen(span, ..)
                 if cfg.fmt.max_width.is_some()
                     && cfg.fmt.force_single_line_if == Some(true)
                     && cfg.fmt.newline_format_if.is_some()
@@ -407,7 +596,7 @@ impl Node {
     fn test_oneline_function(&self, f: &mut String, cfg: &Config, buf: &str, state: &mut State) -> Option<String> {
         use Node::*;
         match self {
-            FuncBody(span, _) | FuncPBody(span, _, _) | FuncBodyB(span, _, _) | FuncPBodyB(span, _, _, _)
+            FuncBody(span, ..)
                 if cfg.fmt.max_width.is_some()
                     && cfg.fmt.newline_format_function.is_some()
                     && span.1 - span.0 < 2 * cfg.fmt.max_width.unwrap_or(0)
@@ -476,6 +665,27 @@ impl Node {
     }
 }
 
+/// Returns the optional part with the gap before it, `pos` is the end of the previous token, it is moved to the end of
+/// the part
+pub fn take_gap<T>(pos: &mut usize, part: Option<(usize, T, usize)>) -> Option<(Loc, T)> {
+    part.map(|(l, part, r)| {
+        let gap = Loc(*pos, l);
+        *pos = r;
+        (gap, part)
+    })
+}
+
+// the optional block after `then`, `else` or `do` is indented on its own lines
+fn write_block(f: &mut String, cfg: &Config, buf: &str, state: &mut State, block: &Part, nl: bool) -> std::fmt::Result {
+    match block {
+        Some((loc, b)) => {
+            #[cfg_attr(rustfmt, rustfmt_skip)]
+            cfg_write!(f, cfg, buf, state, IncIndent(None), IfNewLine(nl, CommentLocHint(loc, " ")), b, DecIndent())
+        }
+        None => Ok(()),
+    }
+}
+
 impl ConfiguredWrite for Node {
     fn configured_write(&self, f: &mut String, cfg: &Config, buf: &str, state: &mut State) -> std::fmt::Result {
         use Node::*;
@@ -548,6 +758,7 @@ impl ConfiguredWrite for Node {
             True(_) => write!(f, "true"),
             VarArg(_) => write!(f, "..."),
             Break(_) => write!(f, "break"),
+            Continue(_) => write!(f, "continue"),
 
             // literals
             Numeral(_, s) => write!(f, "{}", s),
@@ -557,6 +768,7 @@ impl ConfiguredWrite for Node {
                 let level_str = (0..*level).map(|_| "=").collect::<String>();
                 write!(f, "[{}[{}]{}]", level_str, s, level_str)
             }
+            InterpolatedStringLiteral(_, s) => write!(f, "`{}`", s),
 
             TableConstructor(span, locs, n, opts) => {
                 out_of_range_write!(f, cfg, buf, state, span, "{{", locs[0], n, locs[1], "}}");
@@ -660,6 +872,16 @@ impl ConfiguredWrite for Node {
                 cfg_write_sep_list(f, cfg, buf, state, self)?;
                 Ok(())
             }
+            AttName(span, locs, n1, n2) => {
+                out_of_range_write!(f, cfg, buf, state, span, n1, locs[0], "<", locs[1], n2, locs[2], ">");
+
+                let hint_before = cfg.fmt.hint_before_attrib.as_deref().unwrap_or(" ");
+                let hint_inside = cfg.fmt.hint_inside_attrib.as_deref().unwrap_or("");
+
+                #[cfg_attr(rustfmt, rustfmt_skip)]
+                cfg_write!(f, cfg, buf, state, n1, Hint(&locs[0], hint_before), "<", Hint(&locs[1], hint_inside), n2,
+                           Hint(&locs[2], hint_inside), ">")
+            }
             VarList(span, _) => {
                 out_of_range_only_write!(f, cfg, buf, state, span);
                 cfg_write_sep_list(f, cfg, buf, state, self)?;
@@ -693,6 +915,10 @@ impl ConfiguredWrite for Node {
                 #[cfg_attr(rustfmt, rustfmt_skip)]
                 cfg_write!(f, cfg, buf, state, If(ind, &IncIndent(None)), Hint(&locs[1], " "), n2, If(ind, &DecIndent()))
             }
+            CompoundAssignment(span, locs, op, n, e) => {
+                out_of_range_write!(f, cfg, buf, state, span, n, locs[0], op, locs[1], e);
+                cfg_write!(f, cfg, buf, state, n, Hint(&locs[0], " "), op, Hint(&locs[1], " "), e)
+            }
 
             VarRoundSuffix(span, locs, n1, n2) => {
                 out_of_range_write!(f, cfg, buf, state, span, "(", locs[0], n1, locs[1], ")");
@@ -725,20 +951,11 @@ impl ConfiguredWrite for Node {
                 out_of_range_write!(f, cfg, buf, state, span, "function", locs[0], n);
                 cfg_write!(f, cfg, buf, state, "function", Hint(&locs[0], ""), n)
             }
-            FuncBody(span, locs) => {
-                out_of_range_write!(f, cfg, buf, state, span, "(", locs[0], ")", locs[1], "end");
-
-                if let Some(line) = self.test_oneline_function(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
-                }
-
-                let nl = cfg.fmt.newline_format_function == Some(1);
-
+            FuncBody(span, locs, pars, ret, b) => {
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "(", Hint(&locs[0], ""), ")", IfNewLine(nl, Hint(&locs[1], " ")), "end")
-            }
-            FuncBodyB(span, locs, n2) => {
-                out_of_range_write!(f, cfg, buf, state, span, "(", locs[0], ")", locs[1], n2, locs[2], "end");
+                out_of_range_write!(f, cfg, buf, state, span, "(", pars, locs[0], ")",
+                                    ret.as_ref().map(|(locs, t)| (&locs[0], (Str(":"), (&locs[1], t)))), b, locs[1],
+                                    "end");
 
                 if let Some(line) = self.test_oneline_function(f, cfg, buf, state) {
                     return write!(f, "{}", line);
@@ -747,37 +964,24 @@ impl ConfiguredWrite for Node {
                 let nl = cfg.fmt.newline_format_function == Some(1);
 
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "(", Hint(&locs[0], ""), ")", IncIndent(None), IncFuncLevel(),
-                           IfNewLine(nl, Hint(&locs[1], " ")), n2, DecIndent(), DecFuncLevel(),
-                           IfNewLine(nl, Hint(&locs[2], " ")), "end")
-            }
-            FuncPBody(span, locs, n1) => {
-                out_of_range_write!(f, cfg, buf, state, span, "(", locs[0], n1, locs[1], ")", locs[2], "end");
+                cfg_write!(f, cfg, buf, state, "(", If(pars.is_some(), &IncIndent(None)),
+                           pars.as_ref().map(|(loc, n)| (Hint(loc, ""), n)), Hint(&locs[0], ""),
+                           If(pars.is_some(), &DecIndent()), ")")?;
 
-                if let Some(line) = self.test_oneline_function(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
+                if let Some(([loc1, loc2], t)) = ret {
+                    cfg_write!(f, cfg, buf, state, Hint(loc1, ""), ":", Hint(loc2, " "), t)?;
                 }
-
-                let nl = cfg.fmt.newline_format_function == Some(1);
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "(", IncIndent(None), Hint(&locs[0], ""), n1, Hint(&locs[1], ""),
-                           DecIndent(), ")", IfNewLine(nl, Hint(&locs[2], " ")), "end")
-            }
-            FuncPBodyB(span, locs, n1, n2) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "(", locs[0], n1, locs[1], ")", locs[2], n2, locs[3], "end");
-
-                if let Some(line) = self.test_oneline_function(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
+                if let Some((loc, b)) = b {
+                    #[cfg_attr(rustfmt, rustfmt_skip)]
+                    cfg_write!(f, cfg, buf, state, IncIndent(None), IncFuncLevel(), IfNewLine(nl, Hint(loc, " ")), b,
+                               DecIndent(), DecFuncLevel())?;
                 }
 
-                let nl = cfg.fmt.newline_format_function == Some(1);
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "(", IncIndent(None), Hint(&locs[0], ""), n1, Hint(&locs[1], ""),
-                           DecIndent(), ")", IncIndent(None), IncFuncLevel(), IfNewLine(nl, Hint(&locs[2], " ")), n2,
-                           DecIndent(), DecFuncLevel(), IfNewLine(nl, Hint(&locs[3], " ")), "end")
+                cfg_write!(f, cfg, buf, state, IfNewLine(nl, Hint(&locs[1], " ")), "end")
+            }
+            GenericFuncBody(span, locs, g, n) => {
+                out_of_range_write!(f, cfg, buf, state, span, g, locs[0], n);
+                cfg_write!(f, cfg, buf, state, g, Hint(&locs[0], ""), n)
             }
             FuncName(span, _) => {
                 out_of_range_only_write!(f, cfg, buf, state, span);
@@ -824,23 +1028,10 @@ impl ConfiguredWrite for Node {
             }
 
             // if
-            IfThen(span, locs, e1) => {
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], "end");
-
-                if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
-                }
-
-                let nl = cfg.fmt.newline_format_if == Some(1);
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           IfNewLine(nl, Hint(&locs[2], " ")), "end")
-            }
-            IfThenB(span, locs, e1, b1) => {
+            IfThen(span, locs, e, b1, n, b2) => {
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], b1, locs[3],
-                                    "end");
+                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e, locs[1], "then", b1, n,
+                                    b2.as_ref().map(|(loc, b)| (loc, (Str("else"), b))), locs[2], "end");
 
                 if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
                     return write!(f, "{}", line);
@@ -849,193 +1040,127 @@ impl ConfiguredWrite for Node {
                 let nl = cfg.fmt.newline_format_if == Some(1);
 
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           IncIndent(None), IfNewLine(nl, Hint(&locs[2], " ")), b1, DecIndent(),
-                           IfNewLine(nl, Hint(&locs[3], " ")), "end")
-            }
-            IfThenElse(span, locs, e1) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], "else",
-                                    locs[3], "end");
+                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e, Hint(&locs[1], " "), "then")?;
 
-                if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
+                write_block(f, cfg, buf, state, b1, nl)?;
+                if let Some((loc, n)) = n {
+                    cfg_write!(f, cfg, buf, state, IfNewLine(nl, Hint(loc, " ")), n)?;
                 }
-
-                let nl = cfg.fmt.newline_format_if == Some(1);
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           Hint(&locs[2], " "), "else", IfNewLine(nl, Hint(&locs[3], " ")), "end")
-            }
-            IfThenBElse(span, locs, e1, b1) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], b1, locs[3],
-                                    "else", locs[4], "end");
-
-                if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
+                if let Some((loc, b)) = b2 {
+                    // `else` right after `then` stays on the same line
+                    let nl_else = nl && (b1.is_some() || n.is_some());
+                    cfg_write!(f, cfg, buf, state, IfNewLine(nl_else, Hint(loc, " ")), "else")?;
+                    write_block(f, cfg, buf, state, b, nl)?;
                 }
 
-                let nl = cfg.fmt.newline_format_if == Some(1);
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           IncIndent(None), IfNewLine(nl, Hint(&locs[2], " ")), b1, DecIndent(),
-                           IfNewLine(nl, Hint(&locs[3], " ")), "else", IfNewLine(nl, Hint(&locs[4], " ")), "end")
+                cfg_write!(f, cfg, buf, state, IfNewLine(nl, Hint(&locs[2], " ")), "end")
             }
-            IfThenElseB(span, locs, e1, b2) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], "else",
-                                    locs[3], b2, locs[4], "end");
-
-                if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
-                }
-
-                let nl = cfg.fmt.newline_format_if == Some(1);
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           Hint(&locs[2], " "), "else", IncIndent(None), IfNewLine(nl, Hint(&locs[3], " ")), b2,
-                           DecIndent(), IfNewLine(nl, Hint(&locs[4], " ")), "end")
+            ElseIfThenVec(span, _) => {
+                out_of_range_only_write!(f, cfg, buf, state, span);
+                cfg_write_list(f, cfg, buf, state, self)?;
+                Ok(())
             }
-            IfThenBElseB(span, locs, e1, b1, b2) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], b1, locs[3],
-                                    "else", locs[4], b2, locs[5], "end");
-
-                if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
-                }
-
-                let nl = cfg.fmt.newline_format_if == Some(1);
+            ElseIfThen(span, locs, e, b) => {
+                out_of_range_write!(f, cfg, buf, state, span, "elseif", locs[0], e, locs[1], "then", b);
 
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           IncIndent(None), IfNewLine(nl, Hint(&locs[2], " ")), b1, DecIndent(),
-                           IfNewLine(nl, Hint(&locs[3], " ")), "else", IncIndent(None),
-                           IfNewLine(nl, Hint(&locs[4], " ")), b2, DecIndent(),
-                           IfNewLine(nl, Hint(&locs[5], " ")), "end")
-            }
-            IfThenElseIf(span, locs, e1, n) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], n, locs[3],
-                                    "end");
-
-                if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
-                }
+                cfg_write!(f, cfg, buf, state, "elseif", Hint(&locs[0], " "), e, Hint(&locs[1], " "), "then")?;
 
                 let nl = cfg.fmt.newline_format_if == Some(1);
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           IfNewLine(nl, Hint(&locs[2], " ")), n, IfNewLine(nl, Hint(&locs[3], " ")), "end")
+                write_block(f, cfg, buf, state, b, nl)
             }
-            IfThenBElseIf(span, locs, e1, b1, n) => {
+            IfThenElseExp(span, locs, e1, e2, n, e3) => {
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], b1, locs[3],
-                                    n, locs[4], "end");
-
-                if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
-                }
-
-                let nl = cfg.fmt.newline_format_if == Some(1);
+                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], e2, n, locs[3],
+                                    "else", locs[4], e3);
 
                 #[cfg_attr(rustfmt, rustfmt_skip)]
                 cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           IncIndent(None), IfNewLine(nl, Hint(&locs[2], " ")), b1, DecIndent(),
-                           IfNewLine(nl, Hint(&locs[3], " ")), n, IfNewLine(nl, Hint(&locs[4], " ")), "end")
+                           Hint(&locs[2], " "), e2, n.as_ref().map(|(loc, n)| (Hint(loc, " "), n)),
+                           Hint(&locs[3], " "), "else", Hint(&locs[4], " "), e3)
             }
-            IfThenElseIfElse(span, locs, e1, n) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], n, locs[3],
-                                    "else", locs[4], "end");
-
-                if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
-                }
-
-                let nl = cfg.fmt.newline_format_if == Some(1);
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           IfNewLine(nl, Hint(&locs[2], " ")), n, IfNewLine(nl, Hint(&locs[3], " ")), "else",
-                           IfNewLine(nl, Hint(&locs[4], " ")), "end")
+            ElseIfThenExpVec(span, _) => {
+                out_of_range_only_write!(f, cfg, buf, state, span);
+                cfg_write_list(f, cfg, buf, state, self)?;
+                Ok(())
             }
-            IfThenBElseIfElse(span, locs, e1, b1, n) => {
+            ElseIfThenExp(span, locs, e1, e2) => {
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], b1, locs[3],
-                                    n, locs[4], "else", locs[5], "end");
-
-                if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
-                }
-
-                let nl = cfg.fmt.newline_format_if == Some(1);
+                out_of_range_write!(f, cfg, buf, state, span, "elseif", locs[0], e1, locs[1], "then", locs[2], e2);
 
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           IncIndent(None), IfNewLine(nl, Hint(&locs[2], " ")), b1, DecIndent(),
-                           IfNewLine(nl, Hint(&locs[3], " ")), n, IfNewLine(nl, Hint(&locs[4], " ")), "else",
-                           IfNewLine(nl, Hint(&locs[5], " ")), "end")
+                cfg_write!(f, cfg, buf, state, "elseif", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
+                           Hint(&locs[2], " "), e2)
             }
-            IfThenElseIfElseB(span, locs, e1, n, b2) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], n, locs[3],
-                                    "else", locs[4], b2, locs[5], "end");
 
-                if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
-                }
-
-                let nl = cfg.fmt.newline_format_if == Some(1);
+            TypeDecl(span, locs, n, t) => {
+                out_of_range_write!(f, cfg, buf, state, span, "type", locs[0], n, locs[1], "=", locs[2], t);
 
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           IfNewLine(nl, Hint(&locs[2], " ")), n, IfNewLine(nl, Hint(&locs[3], " ")), "else",
-                           IncIndent(None), IfNewLine(nl, Hint(&locs[4], " ")), b2, DecIndent(),
-                           IfNewLine(nl, Hint(&locs[5], " ")), "end")
+                cfg_write!(f, cfg, buf, state, "type", Hint(&locs[0], " "), n, Hint(&locs[1], " "), "=",
+                           Hint(&locs[2], " "), t)
             }
-            IfThenBElseIfElseB(span, locs, e1, b1, n, b2) => {
+            ExportTypeDecl(span, locs, n, t) => {
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "if", locs[0], e1, locs[1], "then", locs[2], b1, locs[3],
-                                    n, locs[4], "else", locs[5], b2, locs[6], "end");
-
-                if let Some(line) = self.test_oneline_if(f, cfg, buf, state) {
-                    return write!(f, "{}", line);
-                }
-
-                let nl = cfg.fmt.newline_format_if == Some(1);
+                out_of_range_write!(f, cfg, buf, state, span, "export", locs[0], "type", locs[1], n, locs[2], "=", locs[3],
+                                    t);
 
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "if", Hint(&locs[0], " "), e1, Hint(&locs[1], " "), "then",
-                           IncIndent(None), IfNewLine(nl, Hint(&locs[2], " ")), b1, DecIndent(),
-                           IfNewLine(nl, Hint(&locs[3], " ")), n, IfNewLine(nl, Hint(&locs[4], " ")), "else",
-                           IncIndent(None), IfNewLine(nl, Hint(&locs[5], " ")), b2, DecIndent(),
-                           IfNewLine(nl, Hint(&locs[6], " ")), "end")
+                cfg_write!(f, cfg, buf, state, "export", Hint(&locs[0], " "), "type", Hint(&locs[1], " "), n,
+                           Hint(&locs[2], " "), "=", Hint(&locs[3], " "), t)
             }
-            ElseIfThenVec(span, _) => {
+            TypeAnnotation(span, locs, n, t) => {
+                out_of_range_write!(f, cfg, buf, state, span, n, locs[0], ":", locs[1], t);
+                cfg_write!(f, cfg, buf, state, n, Hint(&locs[0], ""), ":", Hint(&locs[1], " "), t)
+            }
+            TypeCast(span, locs, e, t) => {
+                out_of_range_write!(f, cfg, buf, state, span, e, locs[0], "::", locs[1], t);
+                cfg_write!(f, cfg, buf, state, e, Hint(&locs[0], " "), "::", Hint(&locs[1], " "), t)
+            }
+            TypeOptional(span, locs, t) => {
+                out_of_range_write!(f, cfg, buf, state, span, t, locs[0], "?");
+                cfg_write!(f, cfg, buf, state, t, Hint(&locs[0], ""), "?")
+            }
+            TypeGeneric(span, locs, n, g) => {
+                out_of_range_write!(f, cfg, buf, state, span, n, locs[0], g);
+                cfg_write!(f, cfg, buf, state, n, Hint(&locs[0], ""), g)
+            }
+            Generics(span, locs, n) => {
+                out_of_range_write!(f, cfg, buf, state, span, "<", locs[0], n, locs[1], ">");
+                cfg_write!(f, cfg, buf, state, "<", Hint(&locs[0], ""), n, Hint(&locs[1], ""), ">")
+            }
+            TypeList(span, _) => {
                 out_of_range_only_write!(f, cfg, buf, state, span);
-                cfg_write_list(f, cfg, buf, state, self)?;
+                cfg_write_sep_list(f, cfg, buf, state, self)?;
                 Ok(())
             }
-            ElseIfThen(span, locs, e) => {
-                out_of_range_write!(f, cfg, buf, state, span, "elseif", locs[0], e, locs[1], "then");
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "elseif", Hint(&locs[0], " "), e, Hint(&locs[1], " "), "then")
+            TypeRoundBrackets(span, locs, n) => {
+                out_of_range_write!(f, cfg, buf, state, span, "(", locs[0], n, locs[1], ")");
+                cfg_write!(f, cfg, buf, state, "(", Hint(&locs[0], ""), n, Hint(&locs[1], ""), ")")
             }
-            ElseIfThenB(span, locs, e, b) => {
-                out_of_range_write!(f, cfg, buf, state, span, "elseif", locs[0], e, locs[1], "then", locs[2], b);
-
-                let nl = cfg.fmt.newline_format_if == Some(1);
+            TypeRoundBracketsEmpty(span, locs) => {
+                out_of_range_write!(f, cfg, buf, state, span, "(", locs[0], ")");
+                cfg_write!(f, cfg, buf, state, "(", Hint(&locs[0], ""), ")")
+            }
+            TypeFunction(span, locs, n, t) => {
+                out_of_range_write!(f, cfg, buf, state, span, n, locs[0], "->", locs[1], t);
+                cfg_write!(f, cfg, buf, state, n, Hint(&locs[0], " "), "->", Hint(&locs[1], " "), t)
+            }
+            TypeVarArg(span, locs, t) => {
+                out_of_range_write!(f, cfg, buf, state, span, "...", locs[0], t);
+                cfg_write!(f, cfg, buf, state, "...", Hint(&locs[0], ""), t)
+            }
+            TypePack(span, locs, n) => {
+                out_of_range_write!(f, cfg, buf, state, span, n, locs[0], "...");
+                cfg_write!(f, cfg, buf, state, n, Hint(&locs[0], ""), "...")
+            }
+            TypeOf(span, locs, e) => {
+                #[cfg_attr(rustfmt, rustfmt_skip)]
+                out_of_range_write!(f, cfg, buf, state, span, "typeof", locs[0], "(", locs[1], e, locs[2], ")");
 
                 #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "elseif", Hint(&locs[0], " "), e, Hint(&locs[1], " "), "then",
-                           IncIndent(None), IfNewLine(nl, Hint(&locs[2], " ")), b, DecIndent())
+                cfg_write!(f, cfg, buf, state, "typeof", Hint(&locs[0], ""), "(", Hint(&locs[1], ""), e,
+                           Hint(&locs[2], ""), ")")
             }
 
             Name(_, s) => write!(f, "{}", s),
@@ -1088,75 +1213,32 @@ impl ConfiguredWrite for Node {
                            DecIndent(), IfNewLine(nl, Hint(&locs[1], " ")), "until", Hint(&locs[2], " "), e)
             }
 
-            ForInt(span, locs, n, e1, e2) => {
+            ForInt(span, locs, n, e1, e2, e3, b) => {
                 #[cfg_attr(rustfmt, rustfmt_skip)]
                 out_of_range_write!(f, cfg, buf, state, span, "for", locs[0], n, locs[1], "=", locs[2], e1, locs[3], ",",
-                                    locs[4], e2, locs[5], "do", locs[6], "end");
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "for", Hint(&locs[0], " "), n, Hint(&locs[1], " "), "=",
-                           Hint(&locs[2], " "), e1, Hint(&locs[3], ""), ",", Hint(&locs[4], " "), e2,
-                           Hint(&locs[5], " "), "do", Hint(&locs[6], " "), "end")
-            }
-            ForIntB(span, locs, n, e1, e2, b) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "for", locs[0], n, locs[1], "=", locs[2], e1, locs[3], ",",
-                                    locs[4], e2, locs[5], "do", locs[6], b, locs[7], "end");
+                                    locs[4], e2, e3.as_ref().map(|(locs, e)| (&locs[0], (Str(","), (&locs[1], e)))),
+                                    locs[5], "do", b, locs[6], "end");
 
                 let nl = cfg.fmt.newline_format_for == Some(1);
 
                 #[cfg_attr(rustfmt, rustfmt_skip)]
                 cfg_write!(f, cfg, buf, state, "for", Hint(&locs[0], " "), n, Hint(&locs[1], " "), "=",
-                           Hint(&locs[2], " "), e1, Hint(&locs[3], ""), ",", Hint(&locs[4], " "), e2,
-                           Hint(&locs[5], " "), "do", IncIndent(None), IfNewLine(nl, Hint(&locs[6], " ")), b,
-                           DecIndent(), IfNewLine(nl, Hint(&locs[7], " ")), "end")
-            }
-            ForIntStep(span, locs, n, e1, e2, e3) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "for", locs[0], n, locs[1], "=", locs[2], e1, locs[3], ",",
-                                    locs[4], e2, locs[5], ",", locs[6], e3, locs[7], "do", locs[8], "end");
+                           Hint(&locs[2], " "), e1, Hint(&locs[3], ""), ",", Hint(&locs[4], " "), e2)?;
 
-                let nl = cfg.fmt.newline_format_for == Some(1);
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "for", Hint(&locs[0], " "), n, Hint(&locs[1], " "), "=",
-                           Hint(&locs[2], " "), e1, Hint(&locs[3], ""), ",", Hint(&locs[4], " "), e2, Hint(&locs[5], ""),
-                           ",", Hint(&locs[6], " "), e3, Hint(&locs[7], " "), "do", IfNewLine(nl, Hint(&locs[8], " ")),
-                           "end")
-            }
-            ForIntStepB(span, locs, n, e1, e2, e3, b) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "for", locs[0], n, locs[1], "=", locs[2], e1, locs[3], ",",
-                                    locs[4], e2, locs[5], ",", locs[6], e3, locs[7], "do", locs[8], b, locs[9], "end");
-
-                let nl = cfg.fmt.newline_format_for == Some(1);
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "for", Hint(&locs[0], " "), n, Hint(&locs[1], " "), "=",
-                           Hint(&locs[2], " "), e1, Hint(&locs[3], ""), ",", Hint(&locs[4], " "), e2, Hint(&locs[5], ""),
-                           ",", Hint(&locs[6], " "), e3, Hint(&locs[7], " "), "do", IncIndent(None),
-                           IfNewLine(nl, Hint(&locs[8], " ")), b, DecIndent(), IfNewLine(nl, Hint(&locs[9], " ")), "end")
-            }
-            ForRange(span, locs, n, e) => {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                out_of_range_write!(f, cfg, buf, state, span, "for", locs[0], n, locs[1], "in", locs[2], e, locs[3], "do",
-                                    locs[4], "end");
-
-                let nl = cfg.fmt.newline_format_for == Some(1);
-
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, "for", Hint(&locs[0], " "), n, Hint(&locs[1], " "), "in")?;
-
-                let ind = e.test_indent(f, cfg, buf, state, Hint(&locs[2], " ")) == Ok(true);
+                if let Some(([loc1, loc2], e)) = e3 {
+                    cfg_write!(f, cfg, buf, state, Hint(loc1, ""), ",", Hint(loc2, " "), e)?;
+                }
 
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                cfg_write!(f, cfg, buf, state, If(ind, &IncIndent(None)), Hint(&locs[2], " "), e, If(ind, &DecIndent()),
-                           Hint(&locs[3], " "), "do", IfNewLine(nl, Hint(&locs[4], " ")), "end")
+                cfg_write!(f, cfg, buf, state, Hint(&locs[5], " "), "do")?;
+                write_block(f, cfg, buf, state, b, nl)?;
+                // the empty loop without the step keeps `do end` on the same line
+                let nl_end = nl && (b.is_some() || e3.is_some());
+                cfg_write!(f, cfg, buf, state, IfNewLine(nl_end, Hint(&locs[6], " ")), "end")
             }
-            ForRangeB(span, locs, n, e, b) => {
+            ForRange(span, locs, n, e, b) => {
                 #[cfg_attr(rustfmt, rustfmt_skip)]
                 out_of_range_write!(f, cfg, buf, state, span, "for", locs[0], n, locs[1], "in", locs[2], e, locs[3], "do",
-                                    locs[4], b, locs[5], "end");
+                                    b, locs[4], "end");
 
                 let nl = cfg.fmt.newline_format_for == Some(1);
 
@@ -1166,8 +1248,9 @@ impl ConfiguredWrite for Node {
 
                 #[cfg_attr(rustfmt, rustfmt_skip)]
                 cfg_write!(f, cfg, buf, state, If(ind, &IncIndent(None)), Hint(&locs[2], " "), e, If(ind, &DecIndent()),
-                           Hint(&locs[3], " "), "do", IncIndent(None), IfNewLine(nl, Hint(&locs[4], " ")), b,
-                           DecIndent(), IfNewLine(nl, Hint(&locs[5], " ")), "end")
+                           Hint(&locs[3], " "), "do")?;
+                write_block(f, cfg, buf, state, b, nl)?;
+                cfg_write!(f, cfg, buf, state, IfNewLine(nl, Hint(&locs[4], " ")), "end")
             }
 
             RetStatNone(_) => write!(f, "return"),
@@ -1215,6 +1298,7 @@ impl ConfiguredWrite for Node {
 
             Semicolon(_) => write!(f, ";"),
             SheBang(_, s) => write!(f, "{}\n", s),
+            Unparsed(span) => write!(f, "{}", span.substr(buf, state, 0)),
         }
     }
 }
@@ -1,6 +1,7 @@
 use phf::phf_map;
 use std::fmt;
 
+use super::dialect::Dialect;
 use super::lexer_util::*;
 
 type TChars<'a> = std::iter::Peekable<std::iter::Enumerate<std::str::Chars<'a>>>;
@@ -71,6 +72,16 @@ pub enum Token {
     VarArg,
     While,
 
+    // Luau
+    Typeof,
+    CompoundAssignment(&'static str),
+    Arrow,
+    QuestionMark,
+    TypeCast,
+    TypeUnion,
+    TypeIntersection,
+    InterpolatedStringLiteral(String),
+
     SheBang(String),
     EOF,
 }
@@ -146,6 +157,15 @@ impl fmt::Display for Token {
             VarArg => write!(f, "..."),
             While => write!(f, "while"),
 
+            Typeof => write!(f, "typeof"),
+            CompoundAssignment(op) => write!(f, "{}", op),
+            Arrow => write!(f, "->"),
+            QuestionMark => write!(f, "?"),
+            TypeCast => write!(f, "::"),
+            TypeUnion => write!(f, "|"),
+            TypeIntersection => write!(f, "&"),
+            InterpolatedStringLiteral(s) => write!(f, "`{}`", s),
+
             SheBang(s) => write!(f, "{}\n", s),
             EOF => write!(f, "<EOF>"),
         }
@@ -198,17 +218,49 @@ pub struct Lexer<'input> {
     chars: TChars<'input>,
     input: &'input str,
     at_end: bool,
+    dialect: Dialect,
 }
 
 impl<'input> Lexer<'input> {
+    #[cfg(test)]
     pub fn new(input: &'input str) -> Self {
-        Lexer { chars: input.chars().enumerate().peekable(), input, at_end: false }
+        Lexer::with_dialect(input, Dialect::Lua54)
+    }
+
+    pub fn with_dialect(input: &'input str, dialect: Dialect) -> Self {
+        Lexer { chars: input.chars().enumerate().peekable(), input, at_end: false, dialect }
     }
 
     fn consume_ok(&mut self, l: usize, tok: Token, r: usize) -> Option<Result<(usize, Token, usize), LexicalError>> {
         self.chars.next();
         return Some(Ok((l, tok, r)));
     }
+
+    // the operator is already consumed, it makes the compound assignment of Luau if it is followed by `=`
+    fn compound_or_ok(
+        &mut self, l: usize, op: &'static str, tok: Token, r: usize,
+    ) -> Option<Result<(usize, Token, usize), LexicalError>> {
+        match self.chars.peek() {
+            Some(&(_, '=')) if self.dialect == Dialect::Luau => {
+                self.consume_ok(l, Token::CompoundAssignment(op), r + 1)
+            }
+            _ => Some(Ok((l, tok, r))),
+        }
+    }
+
+    // the numeral is already consumed, LuaJIT numerals may have the suffixes
+    fn numeral_ok(
+        &mut self, l: usize, mut val: String, r: usize,
+    ) -> Option<Result<(usize, Token, usize), LexicalError>> {
+        if self.dialect != Dialect::LuaJIT {
+            return Some(Ok((l, Token::Numeral(val), r)));
+        }
+
+        let is_integer = val.starts_with("0x") || !val.contains(['.', 'e', 'E']);
+        let (end, suffix) = get_luajit_suffix_end(&mut self.chars, r, is_integer);
+        val.push_str(&suffix);
+        Some(Ok((l, Token::Numeral(val), end)))
+    }
 }
 
 impl<'input> Iterator for Lexer<'input> {
@@ -219,6 +271,9 @@ impl<'input> Iterator for Lexer<'input> {
         let ok = |l: usize, tok: Token, r: usize| -> Option<Self::Item> {
             return Some(Ok((l, tok, r)));
         };
+        let luau = self.dialect == Dialect::Luau;
+        let (goto, bitwise_ops, floor_division) =
+            (self.dialect.has_goto(), self.dialect.has_bitwise_ops(), self.dialect.has_floor_division());
 
         loop {
             match self.chars.peek() {
@@ -235,7 +290,10 @@ impl<'input> Iterator for Lexer<'input> {
                     self.chars.next();
                 }
 
-                Some(&(i, '^')) => return self.consume_ok(i, OpExponentiation, i + 1),
+                Some(&(i, '^')) => {
+                    self.chars.next();
+                    return self.compound_or_ok(i, "^=", OpExponentiation, i + 1);
+                }
                 Some(&(i, '#')) => {
                     self.chars.next();
                     match self.chars.peek() {
@@ -249,17 +307,29 @@ impl<'input> Iterator for Lexer<'input> {
                         _ => return ok(i, OpLength, i + 1),
                     }
                 }
-                Some(&(i, '*')) => return self.consume_ok(i, OpMultiplication, i + 1),
-                Some(&(i, '%')) => return self.consume_ok(i, OpModulo, i + 1),
+                Some(&(i, '*')) => {
+                    self.chars.next();
+                    return self.compound_or_ok(i, "*=", OpMultiplication, i + 1);
+                }
+                Some(&(i, '%')) => {
+                    self.chars.next();
+                    return self.compound_or_ok(i, "%=", OpModulo, i + 1);
+                }
                 Some(&(i, '/')) => {
                     self.chars.next();
                     match self.chars.peek() {
-                        Some(&(_, '/')) => return self.consume_ok(i, OpFloorDivision, i + 2),
-                        _ => return ok(i, OpDivision, i + 1),
+                        Some(&(_, '/')) if floor_division => {
+                            self.chars.next();
+                            return self.compound_or_ok(i, "//=", OpFloorDivision, i + 2);
+                        }
+                        _ => return self.compound_or_ok(i, "/=", OpDivision, i + 1),
                     }
                 }
 
-                Some(&(i, '+')) => return self.consume_ok(i, OpAddition, i + 1),
+                Some(&(i, '+')) => {
+                    self.chars.next();
+                    return self.compound_or_ok(i, "+=", OpAddition, i + 1);
+                }
                 Some(&(i, '-')) => {
                     self.chars.next();
                     match self.chars.peek() {
@@ -272,7 +342,8 @@ impl<'input> Iterator for Lexer<'input> {
 
                             continue;
                         }
-                        _ => return ok(i, Minus, i + 1),
+                        Some(&(_, '>')) if luau => return self.consume_ok(i, Arrow, i + 2),
+                        _ => return self.compound_or_ok(i, "-=", Minus, i + 1),
                     }
                 }
 
@@ -284,7 +355,7 @@ impl<'input> Iterator for Lexer<'input> {
 
                             match self.chars.peek() {
                                 Some(&(_, '.')) => return self.consume_ok(i, VarArg, i + 3),
-                                _ => return ok(i, OpConcatenation, i + 2),
+                                _ => return self.compound_or_ok(i, "..=", OpConcatenation, i + 2),
                             }
                         }
                         Some(&(_, ch)) if ch.is_ascii_digit() => {
@@ -292,7 +363,7 @@ impl<'input> Iterator for Lexer<'input> {
                             val.insert(0, '.');
 
                             match succ {
-                                true => return ok(i, Numeral(val), end),
+                                true => return self.numeral_ok(i, val, end),
                                 false => return Some(Err(LexicalError::UnexpectedEOF)),
                             }
                         }
@@ -303,7 +374,8 @@ impl<'input> Iterator for Lexer<'input> {
                 Some(&(i, '<')) => {
                     self.chars.next();
                     match self.chars.peek() {
-                        Some(&(_, '<')) => return self.consume_ok(i, OpLeftShift, i + 2),
+                        // the dialects without shifts get two comparisons, `>>` closes the nested generics of Luau
+                        Some(&(_, '<')) if bitwise_ops => return self.consume_ok(i, OpLeftShift, i + 2),
                         Some(&(_, '=')) => return self.consume_ok(i, OpLessOrEqual, i + 2),
                         _ => return ok(i, OpLessThan, i + 1),
                     }
@@ -312,21 +384,26 @@ impl<'input> Iterator for Lexer<'input> {
                 Some(&(i, '>')) => {
                     self.chars.next();
                     match self.chars.peek() {
-                        Some(&(_, '>')) => return self.consume_ok(i, OpRightShift, i + 2),
+                        Some(&(_, '>')) if bitwise_ops => return self.consume_ok(i, OpRightShift, i + 2),
                         Some(&(_, '=')) => return self.consume_ok(i, OpGreaterOrEqual, i + 2),
                         _ => return ok(i, OpGreaterThan, i + 1),
                     }
                 }
 
-                Some(&(i, '&')) => return self.consume_ok(i, OpBitwiseAnd, i + 1),
+                // the types of Luau are joined by `&` and `|`, the bitwise operators appeared in Lua 5.3
+                Some(&(i, '&')) if luau => return self.consume_ok(i, TypeIntersection, i + 1),
+                Some(&(i, '&')) if bitwise_ops => return self.consume_ok(i, OpBitwiseAnd, i + 1),
                 Some(&(i, '~')) => {
                     self.chars.next();
                     match self.chars.peek() {
                         Some(&(_, '=')) => return self.consume_ok(i, OpInequality, i + 2),
+                        _ if !bitwise_ops => return Some(Err(LexicalError::UnrecognizedSymbol(i, '~'))),
                         _ => return ok(i, Tilde, i + 1),
                     }
                 }
-                Some(&(i, '|')) => return self.consume_ok(i, OpBitwiseOr, i + 1),
+                Some(&(i, '|')) if luau => return self.consume_ok(i, TypeUnion, i + 1),
+                Some(&(i, '|')) if bitwise_ops => return self.consume_ok(i, OpBitwiseOr, i + 1),
+                Some(&(i, '?')) if luau => return self.consume_ok(i, QuestionMark, i + 1),
 
                 Some(&(i, '=')) => {
                     self.chars.next();
@@ -341,7 +418,8 @@ impl<'input> Iterator for Lexer<'input> {
                 Some(&(i, ':')) => {
                     self.chars.next();
                     match self.chars.peek() {
-                        Some(&(_, ':')) => return self.consume_ok(i, Label, i + 2),
+                        Some(&(_, ':')) if luau => return self.consume_ok(i, TypeCast, i + 2),
+                        Some(&(_, ':')) if goto => return self.consume_ok(i, Label, i + 2),
                         _ => return ok(i, Colon, i + 1),
                     }
                 }
@@ -403,6 +481,15 @@ impl<'input> Iterator for Lexer<'input> {
                     }
                 }
 
+                Some(&(i, '`')) if luau => {
+                    self.chars.next();
+                    let (_, token_end, succ, val) = get_interpolated_string_ends(&mut self.chars, i + 1);
+                    match succ {
+                        true => return ok(i, InterpolatedStringLiteral(val), token_end),
+                        false => return Some(Err(LexicalError::UnexpectedEOF)),
+                    }
+                }
+
                 Some(&(i, '0')) => {
                     self.chars.next();
                     match self.chars.peek() {
@@ -412,7 +499,7 @@ impl<'input> Iterator for Lexer<'input> {
                             val.insert_str(0, "0x");
 
                             match succ {
-                                true => return ok(i, Numeral(val), end),
+                                true => return self.numeral_ok(i, val, end),
                                 false => return Some(Err(LexicalError::UnexpectedEOF)),
                             }
                         }
@@ -421,7 +508,7 @@ impl<'input> Iterator for Lexer<'input> {
                             val.insert(0, '0');
 
                             match succ {
-                                true => return ok(i, Numeral(val), end),
+                                true => return self.numeral_ok(i, val, end),
                                 false => return Some(Err(LexicalError::UnexpectedEOF)),
                             }
                         }
@@ -434,7 +521,7 @@ impl<'input> Iterator for Lexer<'input> {
                     val.insert(0, ch);
 
                     match succ {
-                        true => return ok(i, Numeral(val), end),
+                        true => return self.numeral_ok(i, val, end),
                         false => return Some(Err(LexicalError::UnexpectedEOF)),
                     }
                 }
@@ -443,6 +530,12 @@ impl<'input> Iterator for Lexer<'input> {
                     let (end, succ, val) = get_variable_end(&mut self.chars, i);
                     match succ {
                         true => {
+                            match (self.dialect, &val[..]) {
+                                (Dialect::Luau, "typeof") => return ok(i, Typeof, end),
+                                (_, "goto") if !goto => return ok(i, Variable(val), end),
+                                _ => {}
+                            }
+
                             match KEYWORDS.get(&val[..]) {
                                 Some(w) => return ok(i, w.clone(), end),
                                 _ => return ok(i, Variable(val), end),
@@ -723,3 +816,198 @@ fn test_lua_lexer_errors() {
         )
     );
 }
+
+#[test]
+fn test_luau_lexer() {
+    type TRes<'a> = Vec<Result<(usize, Token, usize), LexicalError>>;
+    use Token::*;
+
+    let tokens = Lexer::with_dialect("a += 1 :: T", Dialect::Luau).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, Variable("a".to_string()), 1)),
+            Ok((2, CompoundAssignment("+="), 4)),
+            Ok((5, Numeral("1".to_string()), 6)),
+            Ok((7, TypeCast, 9)),
+            Ok((10, Variable("T".to_string()), 11)),
+            Ok((11, EOF, 11))
+        )
+    );
+
+    let tokens = Lexer::with_dialect("a ..= b //= c", Dialect::Luau).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, Variable("a".to_string()), 1)),
+            Ok((2, CompoundAssignment("..="), 5)),
+            Ok((6, Variable("b".to_string()), 7)),
+            Ok((8, CompoundAssignment("//="), 11)),
+            Ok((12, Variable("c".to_string()), 13)),
+            Ok((13, EOF, 13))
+        )
+    );
+
+    let tokens = Lexer::with_dialect("(T?) -> A | B & C", Dialect::Luau).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, OpenRoundBracket, 1)),
+            Ok((1, Variable("T".to_string()), 2)),
+            Ok((2, QuestionMark, 3)),
+            Ok((3, CloseRoundBracket, 4)),
+            Ok((5, Arrow, 7)),
+            Ok((8, Variable("A".to_string()), 9)),
+            Ok((10, TypeUnion, 11)),
+            Ok((12, Variable("B".to_string()), 13)),
+            Ok((14, TypeIntersection, 15)),
+            Ok((16, Variable("C".to_string()), 17)),
+            Ok((17, EOF, 17))
+        )
+    );
+
+    let tokens = Lexer::with_dialect("continue typeof goto", Dialect::Luau).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, Variable("continue".to_string()), 8)),
+            Ok((9, Typeof, 15)),
+            Ok((16, Variable("goto".to_string()), 20)),
+            Ok((20, EOF, 20))
+        )
+    );
+
+    let tokens = Lexer::with_dialect("`a {`{b}`} c`", Dialect::Luau).collect::<TRes>();
+    assert_eq!(tokens, vec!(Ok((0, InterpolatedStringLiteral("a {`{b}`} c".to_string()), 13)), Ok((13, EOF, 13))));
+
+    // the tokens of other dialects are split
+    let tokens = Lexer::new("a += 1").collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, Variable("a".to_string()), 1)),
+            Ok((2, OpAddition, 3)),
+            Ok((3, EqualsSign, 4)),
+            Ok((5, Numeral("1".to_string()), 6)),
+            Ok((6, EOF, 6))
+        )
+    );
+
+    let tokens = Lexer::with_dialect("a << b", Dialect::Luau).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, Variable("a".to_string()), 1)),
+            Ok((2, OpLessThan, 3)),
+            Ok((3, OpLessThan, 4)),
+            Ok((5, Variable("b".to_string()), 6)),
+            Ok((6, EOF, 6))
+        )
+    );
+}
+
+#[test]
+fn test_lua_versions_lexer() {
+    type TRes<'a> = Vec<Result<(usize, Token, usize), LexicalError>>;
+    use LexicalError::*;
+    use Token::*;
+
+    let tokens = Lexer::with_dialect("a = 1ULL + 0x10ll * 12i + 1.5i", Dialect::LuaJIT).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, Variable("a".to_string()), 1)),
+            Ok((2, EqualsSign, 3)),
+            Ok((4, Numeral("1ULL".to_string()), 8)),
+            Ok((9, OpAddition, 10)),
+            Ok((11, Numeral("0x10ll".to_string()), 17)),
+            Ok((18, OpMultiplication, 19)),
+            Ok((20, Numeral("12i".to_string()), 23)),
+            Ok((24, OpAddition, 25)),
+            Ok((26, Numeral("1.5i".to_string()), 30)),
+            Ok((30, EOF, 30))
+        )
+    );
+
+    // the floats cannot be 64-bit integers, the suffixes are not recognized by other dialects
+    let tokens = Lexer::with_dialect("1.5LL", Dialect::LuaJIT).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(Ok((0, Numeral("1.5".to_string()), 3)), Ok((3, Variable("LL".to_string()), 5)), Ok((5, EOF, 5)))
+    );
+    let tokens = Lexer::with_dialect("1LL", Dialect::Lua54).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(Ok((0, Numeral("1".to_string()), 1)), Ok((1, Variable("LL".to_string()), 3)), Ok((3, EOF, 3)))
+    );
+
+    let tokens = Lexer::with_dialect("goto l ::l::", Dialect::Lua51).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, Variable("goto".to_string()), 4)),
+            Ok((5, Variable("l".to_string()), 6)),
+            Ok((7, Colon, 8)),
+            Ok((8, Colon, 9)),
+            Ok((9, Variable("l".to_string()), 10)),
+            Ok((10, Colon, 11)),
+            Ok((11, Colon, 12)),
+            Ok((12, EOF, 12))
+        )
+    );
+    let tokens = Lexer::with_dialect("goto l ::l::", Dialect::LuaJIT).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, GoTo, 4)),
+            Ok((5, Variable("l".to_string()), 6)),
+            Ok((7, Label, 9)),
+            Ok((9, Variable("l".to_string()), 10)),
+            Ok((10, Label, 12)),
+            Ok((12, EOF, 12))
+        )
+    );
+
+    let tokens = Lexer::with_dialect("a // b << c ~= d", Dialect::Lua52).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, Variable("a".to_string()), 1)),
+            Ok((2, OpDivision, 3)),
+            Ok((3, OpDivision, 4)),
+            Ok((5, Variable("b".to_string()), 6)),
+            Ok((7, OpLessThan, 8)),
+            Ok((8, OpLessThan, 9)),
+            Ok((10, Variable("c".to_string()), 11)),
+            Ok((12, OpInequality, 14)),
+            Ok((15, Variable("d".to_string()), 16)),
+            Ok((16, EOF, 16))
+        )
+    );
+
+    let tokens = Lexer::with_dialect("a & ~b", Dialect::Lua51).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, Variable("a".to_string()), 1)),
+            Err(UnrecognizedSymbol(2, '&')),
+            Err(UnrecognizedSymbol(4, '~')),
+            Ok((5, Variable("b".to_string()), 6)),
+            Ok((6, EOF, 6))
+        )
+    );
+
+    let tokens = Lexer::with_dialect("a & ~b // c", Dialect::Lua53).collect::<TRes>();
+    assert_eq!(
+        tokens,
+        vec!(
+            Ok((0, Variable("a".to_string()), 1)),
+            Ok((2, OpBitwiseAnd, 3)),
+            Ok((4, Tilde, 5)),
+            Ok((5, Variable("b".to_string()), 6)),
+            Ok((7, OpFloorDivision, 9)),
+            Ok((10, Variable("c".to_string()), 11)),
+            Ok((11, EOF, 11))
+        )
+    );
+}
@@ -217,6 +217,20 @@ fn test_var() {
     assert_eq!(tscln("a,b,c = 4, 4 & 1, func(42)"), Ok("a, b, c = 4, 4 & 1, func(42)".to_string()));
 }
 
+#[test]
+fn test_local_attrib() {
+    assert_eq!(tscln("local a<const>"), Ok("local a <const>".to_string()));
+    assert_eq!(tscln("local a < const > = 5"), Ok("local a <const> = 5".to_string()));
+    assert_eq!(tscln("local f<close> = io.open(n)"), Ok("local f <close> = io.open(n)".to_string()));
+    assert_eq!(tscln("local a<const>,b,c<close> = 1, 2, 3"), Ok("local a <const>, b, c <close> = 1, 2, 3".to_string()));
+    assert_eq!(tscln("local a <> = 1"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(tscln("local a <const, b> = 1"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(tscln("a <const> = 1"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(tscln("local function f <const> () end"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(tscln("for i <const> = 1, 2 do end"), Err(TestError::ErrorWhileParsing));
+    assert_eq!(tsdef("local a--1\n<--[[2]]const--[[3]]>--4\n= 1"), Ok("local a--1\n<--[[2]]const--[[3]]>--4\n= 1".to_string()));
+}
+
 #[test]
 fn test_round_prefix() {
     assert_eq!(tscln("(fn2())()"), Ok("(fn2())()".to_string()));
@@ -487,3 +501,17 @@ fn test_keep_comments_special() {
     assert_eq!(tsdef("#!/usr/bin/lua"), Ok("#!/usr/bin/lua\n".to_string()));
     assert_eq!(tsdef("#!"), Ok("#!\n".to_string()));
 }
+
+#[test]
+fn test_recovery() {
+    use super::{parse_lua_with_recovery, Dialect};
+
+    let src = "a = 1\nif a then b = end\nc = 2\nd = = 3\n";
+    assert!(parse_lua(src).is_err());
+
+    let (_, errors) = parse_lua_with_recovery(src, Dialect::Lua54).unwrap();
+    assert_eq!(errors.len(), 2);
+
+    let (_, errors) = parse_lua_with_recovery("a = 1\n", Dialect::Lua54).unwrap();
+    assert!(errors.is_empty());
+}
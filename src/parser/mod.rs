@@ -1,7 +1,10 @@
 pub mod comment_ast;
 pub mod common;
 pub mod lua_ast;
+pub mod structure;
+pub mod visitor;
 
+mod dialect;
 mod lexer_util;
 
 mod comment_lexer;
@@ -17,17 +20,89 @@ mod lua_lexer;
 mod lua_syntax;
 mod lua_test;
 
+mod recovery;
+mod syntax_error;
+pub use dialect::Dialect;
+pub use lua_lexer::Token;
+pub use syntax_error::SyntaxError;
+
 use lalrpop_util::ParseError;
 
 type LuaParserError = ParseError<usize, lua_lexer::Token, lua_lexer::LexicalError>;
 type CommentParserError = ParseError<usize, comment_lexer::Token, comment_lexer::LexicalError>;
 
 pub fn parse_lua(src: &str) -> Result<lua_ast::Node, LuaParserError> {
-    let lexer = lua_lexer::Lexer::new(src);
-    lua_syntax::ChunkParser::new().parse(src, lexer)
+    parse_lua_with_dialect(src, Dialect::Lua54)
+}
+
+pub fn parse_lua_with_dialect(src: &str, dialect: Dialect) -> Result<lua_ast::Node, LuaParserError> {
+    let lexer = lua_lexer::Lexer::with_dialect(src, dialect);
+    let mut errors = Vec::new();
+    let node =
+        lua_syntax::ChunkParser::new().parse(src, &mut errors, dialect, lexer).map_err(|e| dialect.filter_expected(e))?;
+
+    match errors.into_iter().next() {
+        Some((_, recovery)) => Err(dialect.filter_expected(recovery.error)),
+        None => Ok(node),
+    }
+}
+
+/// Parses the source keeping every top-level statement which fails to parse as `Node::Unparsed`, also returns
+/// the recovered errors. Lexical errors are not recovered.
+pub fn parse_lua_with_recovery(
+    src: &str, dialect: Dialect,
+) -> Result<(lua_ast::Node, Vec<LuaParserError>), LuaParserError> {
+    let lexer = lua_lexer::Lexer::with_dialect(src, dialect);
+    let mut errors = Vec::new();
+    let mut node =
+        lua_syntax::ChunkParser::new().parse(src, &mut errors, dialect, lexer).map_err(|e| dialect.filter_expected(e))?;
+
+    let spans: Vec<common::Loc> = errors.iter().map(|(span, _)| span.clone()).collect();
+    recovery::collapse_unparsed(&mut node, &spans);
+
+    Ok((node, errors.into_iter().map(|(_, recovery)| dialect.filter_expected(recovery.error)).collect()))
 }
 
 pub fn parse_comment(src: &str) -> Result<comment_ast::Node, CommentParserError> {
     let lexer = comment_lexer::Lexer::new(src);
     comment_syntax::ChunkParser::new().parse(src, lexer)
 }
+
+/// Returns the spans of the tokens of the source, or `None` if the source cannot be split into tokens
+pub fn token_locs(src: &str, dialect: Dialect) -> Option<Vec<common::Loc>> {
+    let mut locs = Vec::new();
+    for token in lua_lexer::Lexer::with_dialect(src, dialect) {
+        match token {
+            Ok((_, lua_lexer::Token::EOF, _)) => break,
+            Ok((l, _, r)) => locs.push(common::Loc(l, r)),
+            Err(_) => return None,
+        }
+    }
+
+    Some(locs)
+}
+
+/// Returns the formatting directives from the comments of the source with their absolute positions
+pub fn parse_directives(src: &str, dialect: Dialect) -> Vec<(common::Loc, comment_ast::Directive)> {
+    let chars_to_bytes: Vec<usize> = src.char_indices().map(|(b, _)| b).chain(std::iter::once(src.len())).collect();
+    let mut directives = Vec::new();
+    let mut gap_start = 0;
+
+    for token in lua_lexer::Lexer::with_dialect(src, dialect) {
+        let (l, r) = match token {
+            Ok((l, _, r)) => (l, r),
+            Err(_) => break,
+        };
+
+        if l > gap_start {
+            if let Ok(node) = parse_comment(&src[chars_to_bytes[gap_start]..chars_to_bytes[l]]) {
+                for (loc, directive) in node.directives() {
+                    directives.push((common::Loc(gap_start + loc.0, gap_start + loc.1), directive));
+                }
+            }
+        }
+        gap_start = r;
+    }
+
+    directives
+}
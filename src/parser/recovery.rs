@@ -0,0 +1,52 @@
+use super::common::Loc;
+use super::lua_ast::Node;
+
+fn contains_any(span: &Loc, unparsed: &[Loc]) -> bool {
+    unparsed.iter().any(|loc| loc.0 >= span.0 && loc.1 <= span.1)
+}
+
+fn collapse_statement(node: &mut Node, span: Loc, unparsed: &[Loc]) {
+    match node {
+        Node::Unparsed(..) => {}
+        _ if contains_any(&span, unparsed) => *node = Node::Unparsed(span),
+        _ => {}
+    }
+}
+
+fn collapse_block(node: &mut Node, unparsed: &[Loc]) {
+    use Node::*;
+    match node {
+        StatementList(span, v) => {
+            let ends: Vec<usize> = v.iter().skip(1).map(|(loc, _)| loc.0).chain(std::iter::once(span.1)).collect();
+
+            for ((loc, node), end) in v.iter_mut().zip(ends) {
+                match node {
+                    StatementList(..) => collapse_block(node, unparsed),
+                    _ => collapse_statement(node, Loc(loc.1, end), unparsed),
+                }
+            }
+        }
+        StatsRetStat(_, _, stats, ret) => {
+            collapse_block(stats, unparsed);
+            collapse_block(ret, unparsed);
+        }
+        RetStatNone(span) | RetStatNoneComma(span, _) | RetStatExpr(span, _, _) | RetStatExprComma(span, _, _) => {
+            let span = span.clone();
+            collapse_statement(node, span, unparsed);
+        }
+        _ => {}
+    }
+}
+
+/// The parser recovers from an error at the innermost statement, then the whole top-level statement which contains
+/// the skipped code is replaced with `Node::Unparsed`
+pub fn collapse_unparsed(node: &mut Node, unparsed: &[Loc]) {
+    if unparsed.is_empty() {
+        return;
+    }
+
+    match node {
+        Node::Chunk(_, block, _) | Node::SheBangChunk(_, _, _, block, _) => collapse_block(block, unparsed),
+        _ => {}
+    }
+}
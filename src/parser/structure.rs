@@ -0,0 +1,202 @@
+use super::common::Loc;
+use super::lua_ast::Node;
+
+/// The node of the syntax tree without the positions and the whitespace: the kind of the node with its value, the
+/// lists also have the number of items. The `loc` is kept to report the position of the difference.
+#[derive(Debug)]
+pub struct StructureItem {
+    pub label: String,
+    pub loc: Loc,
+}
+
+// `\'` and `\"` are the same chars in both kinds of strings, `convert_charstring_to_normalstring` changes the escapes
+fn unescape_quotes(s: &str) -> String {
+    let mut result = String::new();
+    let mut chars = s.chars();
+    while let Some(ch) = chars.next() {
+        result.push(ch);
+        if ch == '\\' {
+            match chars.next() {
+                Some(next @ '\'') | Some(next @ '"') => {
+                    result.pop();
+                    result.push(next);
+                }
+                next => result.extend(next),
+            }
+        }
+    }
+    result
+}
+
+// `;` is an empty statement, it does not change the meaning of the code
+fn structure_children(node: &Node) -> impl Iterator<Item = &Node> {
+    node.children().filter(|node| !matches!(node, Node::Semicolon(..)))
+}
+
+fn list(kind: &str, node: &Node) -> String {
+    format!("{}[{}]", kind, structure_children(node).count())
+}
+
+// the optional parts of the node which are present, e.g. `IfThen(block, else)`
+fn with_parts(kind: &str, parts: &[(&str, bool)]) -> String {
+    let present: Vec<&str> = parts.iter().filter(|(_, present)| *present).map(|(part, _)| *part).collect();
+    format!("{}({})", kind, present.join(", "))
+}
+
+// the kind of the node with its value, the children are taken from `Node::children`
+fn label(node: &Node, src: &str) -> String {
+    use Node::*;
+    match node {
+        BinaryOp(_, _, op, ..) => format!("BinaryOp({})", op.0),
+        UnaryOp(_, _, op, _) => format!("UnaryOp({})", op.0),
+        UnaryNot(..) => "UnaryNot".to_string(),
+
+        Var(..) => "Var".to_string(),
+        RoundBrackets(..) => "RoundBrackets".to_string(),
+        ArgsRoundBrackets(..) => "ArgsRoundBrackets".to_string(),
+        ArgsRoundBracketsEmpty(..) => "ArgsRoundBracketsEmpty".to_string(),
+
+        Nil(..) => "Nil".to_string(),
+        False(..) => "False".to_string(),
+        True(..) => "True".to_string(),
+        VarArg(..) => "VarArg".to_string(),
+        Break(..) => "Break".to_string(),
+        Continue(..) => "Continue".to_string(),
+        Numeral(_, s) => format!("Numeral({})", s),
+        NormalStringLiteral(_, s) | CharStringLiteral(_, s) => format!("String({})", unescape_quotes(s)),
+        MultiLineStringLiteral(_, level, s) => format!("MultiLineString({}, {})", level, s),
+        InterpolatedStringLiteral(_, s) => format!("InterpolatedString({})", s),
+
+        TableConstructor(..) => "TableConstructor".to_string(),
+        TableConstructorEmpty(..) => "TableConstructorEmpty".to_string(),
+        Fields(..) => list("Fields", node),
+        FieldNamedBracket(..) => "FieldNamedBracket".to_string(),
+        FieldNamed(..) => "FieldNamed".to_string(),
+        FieldSequential(..) => "FieldSequential".to_string(),
+
+        TableIndex(..) => "TableIndex".to_string(),
+        TableMember(..) => "TableMember".to_string(),
+        ExpList(..) => list("ExpList", node),
+        NameList(..) => list("NameList", node),
+        AttName(..) => "AttName".to_string(),
+        ParList(..) => list("ParList", node),
+        VarList(..) => list("VarList", node),
+        VarRoundSuffix(..) => "VarRoundSuffix".to_string(),
+        VarSuffixList(..) => list("VarSuffixList", node),
+        FnMethodCall(..) => "FnMethodCall".to_string(),
+        FunctionDef(..) => "FunctionDef".to_string(),
+        FuncBody(_, _, pars, ret, b) => {
+            with_parts("FuncBody", &[("pars", pars.is_some()), ("ret", ret.is_some()), ("block", b.is_some())])
+        }
+        GenericFuncBody(..) => "GenericFuncBody".to_string(),
+        FuncName(..) => list("FuncName", node),
+        FuncNameSelf(_, _, v, _) => format!("FuncNameSelf[{}]", v.len()),
+        FuncDecl(..) => "FuncDecl".to_string(),
+        LocalFuncDecl(..) => "LocalFuncDecl".to_string(),
+
+        StatementList(..) => list("StatementList", node),
+        DoEnd(..) => "DoEnd".to_string(),
+        DoBEnd(..) => "DoBEnd".to_string(),
+        VarsExprs(..) => "VarsExprs".to_string(),
+        CompoundAssignment(_, _, op, ..) => format!("CompoundAssignment({})", op.0),
+        Name(_, s) => format!("Name({})", s),
+        Label(..) => "Label".to_string(),
+        GoTo(..) => "GoTo".to_string(),
+        WhileDo(..) => "WhileDo".to_string(),
+        WhileDoB(..) => "WhileDoB".to_string(),
+        RepeatUntil(..) => "RepeatUntil".to_string(),
+        RepeatBUntil(..) => "RepeatBUntil".to_string(),
+        ForInt(_, _, _, _, _, e3, b) => with_parts("ForInt", &[("step", e3.is_some()), ("block", b.is_some())]),
+        ForRange(_, _, _, _, b) => with_parts("ForRange", &[("block", b.is_some())]),
+
+        LocalNames(..) => "LocalNames".to_string(),
+        LocalNamesExprs(..) => "LocalNamesExprs".to_string(),
+
+        IfThen(_, _, _, b1, n, b2) => {
+            let else_block = matches!(b2, Some((_, Some(_))));
+            let parts =
+                [("block", b1.is_some()), ("elseif", n.is_some()), ("else", b2.is_some()), ("else block", else_block)];
+            with_parts("IfThen", &parts)
+        }
+        ElseIfThenVec(..) => list("ElseIfThenVec", node),
+        ElseIfThen(_, _, _, b) => with_parts("ElseIfThen", &[("block", b.is_some())]),
+        IfThenElseExp(_, _, _, _, n, _) => with_parts("IfThenElseExp", &[("elseif", n.is_some())]),
+        ElseIfThenExpVec(..) => list("ElseIfThenExpVec", node),
+        ElseIfThenExp(..) => "ElseIfThenExp".to_string(),
+
+        TypeDecl(..) => "TypeDecl".to_string(),
+        ExportTypeDecl(..) => "ExportTypeDecl".to_string(),
+        TypeAnnotation(..) => "TypeAnnotation".to_string(),
+        TypeCast(..) => "TypeCast".to_string(),
+        TypeOptional(..) => "TypeOptional".to_string(),
+        TypeGeneric(..) => "TypeGeneric".to_string(),
+        Generics(..) => "Generics".to_string(),
+        TypeList(..) => list("TypeList", node),
+        TypeRoundBrackets(..) => "TypeRoundBrackets".to_string(),
+        TypeRoundBracketsEmpty(..) => "TypeRoundBracketsEmpty".to_string(),
+        TypeFunction(..) => "TypeFunction".to_string(),
+        TypeVarArg(..) => "TypeVarArg".to_string(),
+        TypePack(..) => "TypePack".to_string(),
+        TypeOf(..) => "TypeOf".to_string(),
+
+        // the semicolon after `return` is optional
+        RetStatNone(..) | RetStatNoneComma(..) => "RetStatNone".to_string(),
+        RetStatExpr(..) | RetStatExprComma(..) => "RetStatExpr".to_string(),
+        StatsRetStat(..) => "StatsRetStat".to_string(),
+        Chunk(..) => "Chunk".to_string(),
+        SheBangChunk(..) => "SheBangChunk".to_string(),
+        Semicolon(..) => "Semicolon".to_string(),
+        SheBang(_, s) => format!("SheBang({})", s.trim_end()),
+        Unparsed(Loc(l, r)) => {
+            let text: String = src.chars().skip(*l).take(r - l).filter(|ch| !ch.is_whitespace()).collect();
+            format!("Unparsed({})", text)
+        }
+    }
+}
+
+fn push_structure(node: &Node, src: &str, items: &mut Vec<StructureItem>) {
+    items.push(StructureItem { label: label(node, src), loc: node.loc().clone() });
+    for child in structure_children(node) {
+        push_structure(child, src, items);
+    }
+}
+
+/// Returns the nodes of the tree in the pre-order. The trees of two sources have the same structure if their labels
+/// are equal, the positions, the whitespace, the separators of the lists and the quotes of the strings are ignored.
+pub fn structure(node: &Node, src: &str) -> Vec<StructureItem> {
+    let mut items = Vec::new();
+    push_structure(node, src, &mut items);
+    items
+}
+
+/// Returns the first pair of the items which differ, `None` is the missing item of the shorter structure
+pub fn first_difference<'a>(
+    left: &'a [StructureItem], right: &'a [StructureItem],
+) -> Option<(Option<&'a StructureItem>, Option<&'a StructureItem>)> {
+    (0..left.len().max(right.len()))
+        .map(|i| (left.get(i), right.get(i)))
+        .find(|(l, r)| l.map(|item| &item.label) != r.map(|item| &item.label))
+}
+
+#[test]
+fn test_structure() {
+    use super::parse_lua;
+
+    let labels = |src: &str| -> Vec<String> {
+        structure(&parse_lua(src).unwrap(), src).into_iter().map(|item| item.label).collect()
+    };
+
+    assert_eq!(labels("local a = {1; 'x\\'y',}"), labels("local  a={ 1, \"x'y\" }"));
+    assert_eq!(labels("return;"), labels("return"));
+    assert_eq!(labels("a = 1;;b = 2"), labels("a = 1\nb = 2"));
+    assert_ne!(labels("a = 1 + 2 * 3"), labels("a = (1 + 2) * 3"));
+    assert_ne!(labels("f(a, b)"), labels("f(a)(b)"));
+    assert_ne!(labels("a = 'x\\ny'"), labels("a = 'xny'"));
+    assert_ne!(labels("if a then else b() end"), labels("if a then b() end"));
+    assert_eq!(labels("for i = 1, 2 do --[[x]] end"), labels("for i=1,2 do end"));
+
+    let (left, right) = (structure(&parse_lua("a = 1").unwrap(), ""), structure(&parse_lua("a = 2").unwrap(), ""));
+    let (l, r) = first_difference(&left, &right).unwrap();
+    assert_eq!((l.unwrap().label.as_str(), r.unwrap().label.as_str()), ("Numeral(1)", "Numeral(2)"));
+    assert!(first_difference(&left, &left).is_none());
+}
@@ -0,0 +1,193 @@
+use std::fmt;
+
+use lalrpop_util::ParseError;
+
+use super::lua_lexer::{LexicalError, Token};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub message: String,
+    // 1-based line and column of the error position, the column is counted in chars
+    pub line: usize,
+    pub column: usize,
+    // number of chars marked by the caret
+    pub length: usize,
+    pub expected: Vec<String>,
+    pub token: Option<Token>,
+    pub source_line: String,
+    pub file_name: Option<String>,
+}
+
+// the terminal names of lalrpop are quoted, e.g. `"("`, the token classes are named after the grammar terminals
+fn unquote_expected(expected: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for name in expected {
+        let name = match name.as_str() {
+            "LexVariable" => "<name>".to_string(),
+            "Numeral" => "<number>".to_string(),
+            "NormalStringLiteral" | "CharStringLiteral" | "MultiLineStringLiteral" | "InterpolatedStringLiteral" => {
+                "<string>".to_string()
+            }
+            "CompoundOp" => "<compound assignment>".to_string(),
+            "TypeCast" => "::".to_string(),
+            "TypeUnion" => "|".to_string(),
+            "TypeIntersection" => "&".to_string(),
+            "SheBang" => "<shebang>".to_string(),
+            _ => name.trim_matches('"').to_string(),
+        };
+
+        if !result.contains(&name) {
+            result.push(name);
+        }
+    }
+    result
+}
+
+impl SyntaxError {
+    pub fn new(src: &str, pos: usize, length: usize, message: String) -> Self {
+        let mut line = 1;
+        let mut line_start = 0;
+        for (i, ch) in src.chars().enumerate().take(pos) {
+            if ch == '\n' {
+                line += 1;
+                line_start = i + 1;
+            }
+        }
+
+        let source_line: String =
+            src.chars().skip(line_start).take_while(|&ch| ch != '\n').collect::<String>().trim_end().to_string();
+
+        SyntaxError {
+            message,
+            line,
+            column: pos - line_start + 1,
+            length: length.max(1),
+            expected: Vec::new(),
+            token: None,
+            source_line,
+            file_name: None,
+        }
+    }
+
+    pub fn from_parse_error(src: &str, err: ParseError<usize, Token, LexicalError>) -> Self {
+        // the end of file is reported right after the last lexem
+        let eof = src.trim_end().chars().count();
+
+        match err {
+            ParseError::InvalidToken { location } => SyntaxError::new(src, location, 1, "invalid token".to_string()),
+            ParseError::UnrecognizedEOF { expected, .. } => SyntaxError {
+                expected: unquote_expected(expected),
+                ..SyntaxError::new(src, eof, 1, "unexpected end of file".to_string())
+            },
+            ParseError::UnrecognizedToken { token: (_, Token::EOF, _), expected } => SyntaxError {
+                expected: unquote_expected(expected),
+                token: Some(Token::EOF),
+                ..SyntaxError::new(src, eof, 1, "unexpected end of file".to_string())
+            },
+            ParseError::UnrecognizedToken { token: (l, token, r), expected } => SyntaxError {
+                expected: unquote_expected(expected),
+                ..SyntaxError::new(src, l, r - l, format!("unexpected token `{}`", token))
+            }
+            .with_token(token),
+            ParseError::ExtraToken { token: (l, token, r) } => {
+                SyntaxError::new(src, l, r - l, format!("extra token `{}`", token)).with_token(token)
+            }
+            ParseError::User { error: LexicalError::UnrecognizedSymbol(pos, ch) } => {
+                SyntaxError::new(src, pos, 1, format!("unrecognized symbol `{}`", ch))
+            }
+            ParseError::User { error: LexicalError::UnexpectedEOF } => {
+                SyntaxError::new(src, eof, 1, "unexpected end of file".to_string())
+            }
+        }
+    }
+
+    fn with_token(self, token: Token) -> Self {
+        SyntaxError { token: Some(token), ..self }
+    }
+
+    pub fn with_file_name(self, file_name: &str) -> Self {
+        SyntaxError { file_name: Some(file_name.to_string()), ..self }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        match self.expected.len() {
+            0 => {}
+            1 => write!(f, ", expected `{}`", self.expected[0])?,
+            _ => {
+                let expected: Vec<String> = self.expected.iter().map(|s| format!("`{}`", s)).collect();
+                write!(f, ", expected one of {}", expected.join(", "))?
+            }
+        }
+        writeln!(f)?;
+
+        let line_number = self.line.to_string();
+        let margin = " ".repeat(line_number.len());
+
+        match &self.file_name {
+            Some(file_name) => writeln!(f, "{}--> {}:{}:{}", margin, file_name, self.line, self.column)?,
+            None => writeln!(f, "{}--> {}:{}", margin, self.line, self.column)?,
+        }
+        writeln!(f, "{} |", margin)?;
+        writeln!(f, "{} | {}", line_number, self.source_line)?;
+
+        // tabs are kept to align the caret with the source line
+        let indent: String =
+            self.source_line.chars().take(self.column - 1).map(|ch| if ch == '\t' { '\t' } else { ' ' }).collect();
+        write!(f, "{} | {}{}", margin, indent, "^".repeat(self.length))
+    }
+}
+
+#[test]
+fn test_syntax_error() {
+    use super::{parse_lua, parse_lua_with_dialect, Dialect};
+
+    let src = "a = 1\nb = 3 + 22 * ? + 65\n";
+    let err = SyntaxError::from_parse_error(src, parse_lua(src).unwrap_err());
+    assert_eq!((err.line, err.column, err.length), (2, 14, 1));
+    assert_eq!(err.message, "unrecognized symbol `?`");
+    assert_eq!(err.to_string(), "unrecognized symbol `?`\n --> 2:14\n  |\n2 | b = 3 + 22 * ? + 65\n  |              ^");
+
+    let src = "local t = {\n\tf = function() return end end\n}";
+    let err = SyntaxError::from_parse_error(src, parse_lua(src).unwrap_err());
+    assert_eq!((err.line, err.column, err.length), (2, 28, 3));
+    assert_eq!(err.token, Some(Token::End));
+    assert_eq!(err.expected, vec![",", ";", "}"]);
+    assert_eq!(
+        err.with_file_name("t.lua").to_string(),
+        "unexpected token `end`, expected one of `,`, `;`, `}`\n --> t.lua:2:28\n  |\n2 | \tf = function() return end end\n  | \t                          ^^^"
+    );
+
+    let src = "function fn(a)\n    print(a)\n";
+    let err = SyntaxError::from_parse_error(src, parse_lua(src).unwrap_err());
+    assert_eq!((err.line, err.column), (2, 13));
+    assert_eq!(err.token, Some(Token::EOF));
+    assert_eq!(err.expected, vec!["end"]);
+    assert_eq!(
+        err.to_string(),
+        "unexpected end of file, expected `end`\n --> 2:13\n  |\n2 |     print(a)\n  |             ^"
+    );
+
+    let src = "a = 'string\n";
+    let err = SyntaxError::from_parse_error(src, parse_lua(src).unwrap_err());
+    assert_eq!((err.line, err.column, err.source_line.as_str()), (1, 12, "a = 'string"));
+    assert_eq!(err.to_string(), "unexpected end of file\n --> 1:12\n  |\n1 | a = 'string\n  |            ^");
+
+    let src = "option =\n";
+    let err = SyntaxError::from_parse_error(src, parse_lua(src).unwrap_err());
+    assert!(err.expected.contains(&"<name>".to_string()));
+    assert_eq!(err.expected.iter().filter(|s| *s == "<string>").count(), 1);
+
+    // `if` starts the expressions only in Luau
+    let src = "local x = = 2";
+    let err = SyntaxError::from_parse_error(src, parse_lua(src).unwrap_err());
+    assert!(err.expected.contains(&"<name>".to_string()));
+    assert!(!err.expected.contains(&"if".to_string()));
+    let err = SyntaxError::from_parse_error(src, parse_lua_with_dialect(src, Dialect::Luau).unwrap_err());
+    assert!(err.expected.contains(&"if".to_string()));
+    let src = "while a do ) end";
+    let err = SyntaxError::from_parse_error(src, parse_lua(src).unwrap_err());
+    assert!(err.expected.contains(&"if".to_string()));
+}
@@ -0,0 +1,236 @@
+use super::common::Loc;
+use super::lua_ast::Node;
+
+/// Visits the nodes of the syntax tree, the implementations override `visit_node` to inspect the nodes and call `walk`
+/// to continue with the children of the node
+pub trait Visitor {
+    /// Visits the node, the default implementation visits all its children
+    fn visit_node(&mut self, node: &Node) {
+        walk(self, node);
+    }
+}
+
+/// Visits the nodes of the syntax tree which may be changed, the counterpart of `Visitor`
+pub trait VisitorMut {
+    /// Visits the node, the default implementation visits all its children
+    fn visit_node_mut(&mut self, node: &mut Node) {
+        walk_mut(self, node);
+    }
+}
+
+/// Visits the children of the node in the order of the source
+pub fn walk<V: Visitor + ?Sized>(visitor: &mut V, node: &Node) {
+    for child in node.children() {
+        visitor.visit_node(child);
+    }
+}
+
+/// Visits the children of the node in the order of the source
+pub fn walk_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut Node) {
+    for child in node.children_mut() {
+        visitor.visit_node_mut(child);
+    }
+}
+
+// the iterators over the children of both mutabilities: the items of the lists, then the other child nodes
+macro_rules! children_iterator {
+    ($(#[$attr:meta])* $name:ident, $iter:ident, $($ref:tt)+) => {
+        $(#[$attr])*
+        pub struct $name<'a> {
+            separated: std::slice::$iter<'a, (Loc, Node, Loc, String)>,
+            items: std::slice::$iter<'a, (Loc, Node)>,
+            nodes: std::array::IntoIter<Option<$($ref)+ Node>, 5>,
+        }
+
+        impl<'a> $name<'a> {
+            fn new(
+                separated: std::slice::$iter<'a, (Loc, Node, Loc, String)>, items: std::slice::$iter<'a, (Loc, Node)>,
+                nodes: [Option<$($ref)+ Node>; 5],
+            ) -> Self {
+                $name { separated, items, nodes: IntoIterator::into_iter(nodes) }
+            }
+
+            fn nodes(nodes: [Option<$($ref)+ Node>; 5]) -> Self {
+                Self::new(Default::default(), Default::default(), nodes)
+            }
+        }
+
+        impl<'a> Iterator for $name<'a> {
+            type Item = $($ref)+ Node;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                if let Some((_, node, _, _)) = self.separated.next() {
+                    return Some(node);
+                }
+                if let Some((_, node)) = self.items.next() {
+                    return Some(node);
+                }
+                self.nodes.by_ref().flatten().next()
+            }
+        }
+    };
+}
+
+children_iterator!(
+    /// The iterator over the child nodes in the order of the source, it does not allocate
+    Children,
+    Iter,
+    &'a
+);
+children_iterator!(
+    /// The iterator over the mutable child nodes in the order of the source, it does not allocate
+    ChildrenMut,
+    IterMut,
+    &'a mut
+);
+
+// the children of both mutabilities, every variant is listed to keep the traversal in sync with the tree
+macro_rules! children {
+    ($node:expr, $children:ident, $iter:ident, $as_ref:ident) => {{
+        use Node::*;
+
+        match $node {
+            UnaryOp(_, _, _, n)
+            | UnaryNot(_, _, n)
+            | RoundBrackets(_, _, n)
+            | ArgsRoundBrackets(_, _, n)
+            | TableConstructor(_, _, n, _)
+            | FieldSequential(_, n)
+            | TableIndex(_, _, n)
+            | TableMember(_, _, n)
+            | FunctionDef(_, _, n)
+            | DoBEnd(_, _, n)
+            | Label(_, _, n)
+            | GoTo(_, _, n)
+            | WhileDo(_, _, n)
+            | RepeatUntil(_, _, n)
+            | LocalNames(_, _, n)
+            | TypeOptional(_, _, n)
+            | Generics(_, _, n)
+            | TypeRoundBrackets(_, _, n)
+            | TypeVarArg(_, _, n)
+            | TypePack(_, _, n)
+            | TypeOf(_, _, n)
+            | RetStatExpr(_, _, n)
+            | RetStatExprComma(_, _, n)
+            | Chunk(_, n, _) => $children::nodes([Some(n.$as_ref()), None, None, None, None]),
+
+            BinaryOp(_, _, _, n1, n2)
+            | Var(_, _, n1, n2)
+            | FieldNamedBracket(_, _, n1, n2)
+            | FieldNamed(_, _, n1, n2)
+            | AttName(_, _, n1, n2)
+            | VarRoundSuffix(_, _, n1, n2)
+            | FnMethodCall(_, _, n1, n2)
+            | GenericFuncBody(_, _, n1, n2)
+            | FuncDecl(_, _, n1, n2)
+            | LocalFuncDecl(_, _, n1, n2)
+            | VarsExprs(_, _, n1, n2)
+            | CompoundAssignment(_, _, _, n1, n2)
+            | WhileDoB(_, _, n1, n2)
+            | RepeatBUntil(_, _, n1, n2)
+            | LocalNamesExprs(_, _, n1, n2)
+            | ElseIfThenExp(_, _, n1, n2)
+            | TypeDecl(_, _, n1, n2)
+            | ExportTypeDecl(_, _, n1, n2)
+            | TypeAnnotation(_, _, n1, n2)
+            | TypeCast(_, _, n1, n2)
+            | TypeGeneric(_, _, n1, n2)
+            | TypeFunction(_, _, n1, n2)
+            | StatsRetStat(_, _, n1, n2)
+            | SheBangChunk(_, n1, _, n2, _) => {
+                $children::nodes([Some(n1.$as_ref()), Some(n2.$as_ref()), None, None, None])
+            }
+
+            FuncBody(_, _, pars, ret, b) => $children::nodes([
+                pars.$as_ref().map(|(_, n)| n.$as_ref()),
+                ret.$as_ref().map(|(_, n)| n.$as_ref()),
+                b.$as_ref().map(|(_, n)| n.$as_ref()),
+                None,
+                None,
+            ]),
+            ForInt(_, _, n, e1, e2, e3, b) => $children::nodes([
+                Some(n.$as_ref()),
+                Some(e1.$as_ref()),
+                Some(e2.$as_ref()),
+                e3.$as_ref().map(|(_, n)| n.$as_ref()),
+                b.$as_ref().map(|(_, n)| n.$as_ref()),
+            ]),
+            ForRange(_, _, n, e, b) => $children::nodes([
+                Some(n.$as_ref()),
+                Some(e.$as_ref()),
+                b.$as_ref().map(|(_, n)| n.$as_ref()),
+                None,
+                None,
+            ]),
+            IfThen(_, _, e, b1, n, b2) => $children::nodes([
+                Some(e.$as_ref()),
+                b1.$as_ref().map(|(_, n)| n.$as_ref()),
+                n.$as_ref().map(|(_, n)| n.$as_ref()),
+                b2.$as_ref().and_then(|(_, b)| b.$as_ref()).map(|(_, n)| n.$as_ref()),
+                None,
+            ]),
+            ElseIfThen(_, _, e, b) => {
+                $children::nodes([Some(e.$as_ref()), b.$as_ref().map(|(_, n)| n.$as_ref()), None, None, None])
+            }
+            IfThenElseExp(_, _, e1, e2, n, e3) => $children::nodes([
+                Some(e1.$as_ref()),
+                Some(e2.$as_ref()),
+                n.$as_ref().map(|(_, n)| n.$as_ref()),
+                Some(e3.$as_ref()),
+                None,
+            ]),
+
+            Fields(_, items, _)
+            | ExpList(_, items)
+            | NameList(_, items)
+            | ParList(_, items)
+            | VarList(_, items)
+            | FuncName(_, items)
+            | TypeList(_, items) => $children::new(items.$iter(), Default::default(), [None, None, None, None, None]),
+            VarSuffixList(_, items)
+            | StatementList(_, items)
+            | ElseIfThenVec(_, items)
+            | ElseIfThenExpVec(_, items) => {
+                $children::new(Default::default(), items.$iter(), [None, None, None, None, None])
+            }
+            FuncNameSelf(_, _, items, n) => {
+                $children::new(items.$iter(), Default::default(), [Some(n.$as_ref()), None, None, None, None])
+            }
+
+            ArgsRoundBracketsEmpty(..)
+            | Nil(..)
+            | False(..)
+            | True(..)
+            | VarArg(..)
+            | Break(..)
+            | Continue(..)
+            | Numeral(..)
+            | NormalStringLiteral(..)
+            | CharStringLiteral(..)
+            | MultiLineStringLiteral(..)
+            | InterpolatedStringLiteral(..)
+            | TableConstructorEmpty(..)
+            | DoEnd(..)
+            | Name(..)
+            | TypeRoundBracketsEmpty(..)
+            | RetStatNone(..)
+            | RetStatNoneComma(..)
+            | Semicolon(..)
+            | SheBang(..)
+            | Unparsed(..) => $children::nodes([None, None, None, None, None]),
+        }
+    }};
+}
+
+impl Node {
+    /// Returns the child nodes in the order of the source
+    pub fn children(&self) -> Children<'_> {
+        children!(self, Children, iter, as_ref)
+    }
+
+    /// Returns the child nodes in the order of the source
+    pub fn children_mut(&mut self) -> ChildrenMut<'_> {
+        children!(self, ChildrenMut, iter_mut, as_mut)
+    }
+}
@@ -0,0 +1,105 @@
+use crate::config::FormatOpts;
+
+/// Names of the built-in styles, the `style` option selects one of them
+pub static STYLES: [&str; 4] = ["default", "compact", "roblox-like", "minified"];
+
+// the options of the default `.luafmt.lua`
+fn default_style() -> FormatOpts {
+    FormatOpts {
+        replace_zero_spaces_with_hint: Some(true),
+        hint_after_multiline_comment: Some(" ".to_string()),
+        hint_after_multiline_comment_text: Some(" ".to_string()),
+        hint_before_comment: Some(" ".to_string()),
+        hint_before_multiline_comment_text: Some(" ".to_string()),
+        hint_before_oneline_comment_text: Some(" ".to_string()),
+        hint_table_constructor: Some(" ".to_string()),
+
+        remove_single_newlines: Some(true),
+        remove_spaces_between_tokens: Some(true),
+        write_newline_at_eof: Some(true),
+        write_newline_at_multiline_table: Some(false),
+        write_newline_at_explist_multiline_table: Some(true),
+
+        indentation_string: Some("    ".to_string()),
+        indent_var_suffix: Some(true),
+        indent_exp_list: Some(true),
+
+        newline_format_oneline_comment: Some(1),
+        newline_format_statement: Some(1),
+        newline_format_do_end: Some(1),
+        newline_format_for: Some(1),
+        newline_format_function: Some(1),
+        newline_format_if: Some(1),
+        newline_format_repeat_until: Some(1),
+        newline_format_table_constructor: Some(1),
+        newline_format_table_field: Some(1),
+        newline_format_while: Some(1),
+        newline_format_binary_op: Some(1),
+        newline_format_var_suffix: Some(1),
+        newline_format_exp_list: Some(1),
+
+        field_separator: Some(",".to_string()),
+        write_trailing_field_separator: Some(true),
+        convert_charstring_to_normalstring: Some(false),
+
+        max_width: Some(110),
+        force_single_line_table: Some(true),
+        force_single_line_iv_table_field: Some(false),
+        force_single_line_kv_table_field: Some(false),
+        force_single_line_binary_op: Some(true),
+        force_single_line_if: Some(true),
+        force_single_line_scoped_function: Some(true),
+        force_single_line_var_suffix: Some(true),
+        force_single_line_exp_list: Some(true),
+
+        ..FormatOpts::default()
+    }
+}
+
+/// Returns the format options of the built-in style
+pub fn preset(name: &str) -> Option<FormatOpts> {
+    match name {
+        "default" => Some(default_style()),
+
+        // two spaces, no spaces inside of braces, more constructs on a single line
+        "compact" => Some(FormatOpts {
+            hint_table_constructor: Some("".to_string()),
+            indentation_string: Some("  ".to_string()),
+            max_width: Some(120),
+            force_single_line_iv_table_field: Some(true),
+            force_single_line_kv_table_field: Some(true),
+            force_single_line_top_level_function: Some(true),
+            ..default_style()
+        }),
+
+        // tabs, double quoted strings, multiline `if` statements
+        "roblox-like" => Some(FormatOpts {
+            indentation_string: Some("\t".to_string()),
+            max_width: Some(120),
+            convert_charstring_to_normalstring: Some(true),
+            force_single_line_if: Some(false),
+            ..default_style()
+        }),
+
+        // the whole chunk on a single line without comments
+        "minified" => Some(FormatOpts {
+            replace_zero_spaces_with_hint: Some(true),
+            remove_comments: Some(true),
+            remove_all_newlines: Some(true),
+            remove_spaces_between_tokens: Some(true),
+            remove_optional_spaces: Some(true),
+            ..FormatOpts::default()
+        }),
+
+        _ => None,
+    }
+}
+
+#[test]
+fn test_preset() {
+    for name in STYLES.iter() {
+        assert!(preset(name).is_some(), "{}", name);
+    }
+    assert_eq!(preset("unknown"), None);
+    assert_eq!(preset("compact").and_then(|fmt| fmt.indentation_string), Some("  ".to_string()));
+}
@@ -0,0 +1,375 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// TOML value, only the types which are used by the configuration files are supported
+#[derive(Debug, PartialEq, Clone)]
+pub enum Toml {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Toml>),
+    Table(TomlPairs),
+}
+
+/// Key/value pairs with the numbers of the lines where they start
+pub type TomlPairs = Vec<(String, Toml, usize)>;
+
+impl Toml {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Toml::Bool(_) => "boolean",
+            Toml::Integer(_) => "integer",
+            Toml::Float(_) => "float",
+            Toml::String(_) => "string",
+            Toml::Array(_) => "array",
+            Toml::Table(_) => "table",
+        }
+    }
+}
+
+// the chars of the source with the number of the current line
+struct TChars<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl TChars<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().cloned()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.chars.next();
+        if ch == Some('\n') {
+            self.line += 1;
+        }
+        ch
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.next();
+        }
+    }
+
+    // skips the spaces, newlines and comments
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ') | Some('\t') | Some('\r') | Some('\n') => {
+                    self.next();
+                }
+                Some('#') => {
+                    while !matches!(self.peek(), Some('\n') | None) {
+                        self.next();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+}
+
+// the table which is being read, `[name]` or `[[name]]` if it is an item of the array of tables
+struct Table {
+    name: String,
+    pairs: TomlPairs,
+    line: usize,
+    is_array_item: bool,
+}
+
+/// Parses the key/value pairs of the document, returns them with the numbers of the lines where they start. The
+/// `[name]` tables and the `[[name]]` arrays of tables are supported, the dotted keys are not.
+pub fn parse(src: &str) -> Result<TomlPairs, String> {
+    let mut chars = TChars { chars: src.chars().peekable(), line: 1 };
+    let mut pairs = TomlPairs::new();
+    let mut table: Option<Table> = None;
+
+    let result = loop {
+        chars.skip_blank();
+        let line = chars.line;
+        match chars.peek() {
+            None => break Ok(()),
+            Some('[') => {
+                if let Some(table) = table.take() {
+                    push_table(&mut pairs, table);
+                }
+                match parse_header(&mut chars, &pairs, line) {
+                    Ok(header) => table = Some(header),
+                    Err(err) => break Err(err),
+                }
+                continue;
+            }
+            Some(_) => {}
+        }
+
+        let current = match table.as_mut() {
+            Some(table) => &mut table.pairs,
+            None => &mut pairs,
+        };
+        match parse_pair(&mut chars) {
+            Ok((key, _)) if current.iter().any(|(k, _, _)| k == &key) => break Err(format!("duplicate key `{}`", key)),
+            Ok((key, value)) => current.push((key, value, line)),
+            Err(err) => break Err(err),
+        }
+    };
+
+    if let Some(table) = table.take() {
+        push_table(&mut pairs, table);
+    }
+
+    match result {
+        Ok(_) => Ok(pairs),
+        Err(err) => Err(format!("{} at line {}", err, chars.line)),
+    }
+}
+
+fn parse_header(chars: &mut TChars, pairs: &TomlPairs, line: usize) -> Result<Table, String> {
+    chars.next();
+    let is_array_item = chars.peek() == Some('[');
+    if is_array_item {
+        chars.next();
+    }
+
+    chars.skip_spaces();
+    let name = parse_key(chars)?;
+    chars.skip_spaces();
+
+    let closing = if is_array_item { "]]" } else { "]" };
+    if !closing.chars().all(|expected| chars.next() == Some(expected)) {
+        return Err(format!("expected `{}` after the table name", closing));
+    }
+    chars.skip_spaces();
+    if let Some(ch) = chars.peek().filter(|ch| !"#\r\n".contains(*ch)) {
+        return Err(format!("unexpected character `{}` after the table name", ch));
+    }
+
+    match pairs.iter().find(|(k, _, _)| k == &name) {
+        None => Ok(Table { name, pairs: TomlPairs::new(), line, is_array_item }),
+        Some((_, Toml::Array(items), _)) if is_array_item && items.iter().all(|item| item.type_name() == "table") => {
+            Ok(Table { name, pairs: TomlPairs::new(), line, is_array_item })
+        }
+        Some(_) => Err(format!("duplicate key `{}`", name)),
+    }
+}
+
+fn push_table(pairs: &mut TomlPairs, table: Table) {
+    if !table.is_array_item {
+        pairs.push((table.name, Toml::Table(table.pairs), table.line));
+        return;
+    }
+
+    match pairs.iter_mut().find(|(k, _, _)| k == &table.name) {
+        Some((_, Toml::Array(items), _)) => items.push(Toml::Table(table.pairs)),
+        _ => pairs.push((table.name, Toml::Array(vec![Toml::Table(table.pairs)]), table.line)),
+    }
+}
+
+fn parse_pair(chars: &mut TChars) -> Result<(String, Toml), String> {
+    let key = parse_key(chars)?;
+
+    chars.skip_spaces();
+    match chars.next() {
+        Some('=') => {}
+        Some('.') => return Err("dotted keys are not supported".to_string()),
+        _ => return Err(format!("expected `=` after the key `{}`", key)),
+    }
+    chars.skip_spaces();
+    let value = parse_value(chars)?;
+
+    chars.skip_spaces();
+    match chars.peek() {
+        Some('#') | Some('\r') | Some('\n') | None => Ok((key, value)),
+        Some(ch) => Err(format!("unexpected character `{}` after the value", ch)),
+    }
+}
+
+fn parse_key(chars: &mut TChars) -> Result<String, String> {
+    match chars.peek() {
+        Some('"') => parse_basic_string(chars),
+        Some('\'') => parse_literal_string(chars),
+        _ => {
+            let mut key = String::new();
+            while let Some(ch) = chars.peek() {
+                match ch.is_ascii_alphanumeric() || ch == '_' || ch == '-' {
+                    true => key.push(ch),
+                    false => break,
+                }
+                chars.next();
+            }
+
+            match key.is_empty() {
+                true => Err("expected a key".to_string()),
+                false => Ok(key),
+            }
+        }
+    }
+}
+
+fn parse_value(chars: &mut TChars) -> Result<Toml, String> {
+    match chars.peek() {
+        Some('"') => Ok(Toml::String(parse_basic_string(chars)?)),
+        Some('\'') => Ok(Toml::String(parse_literal_string(chars)?)),
+        Some('[') => parse_array(chars),
+        Some('{') => Err("inline tables are not supported".to_string()),
+        Some(ch) if ch.is_ascii_alphanumeric() || ch == '+' || ch == '-' => {
+            let mut word = String::new();
+            while let Some(ch) = chars.peek() {
+                match ch.is_ascii_alphanumeric() || "+-._".contains(ch) {
+                    true => word.push(ch),
+                    false => break,
+                }
+                chars.next();
+            }
+
+            let number = word.replace('_', "");
+            match word.as_str() {
+                "true" => Ok(Toml::Bool(true)),
+                "false" => Ok(Toml::Bool(false)),
+                _ => match (number.parse::<i64>(), number.parse::<f64>()) {
+                    (Ok(value), _) => Ok(Toml::Integer(value)),
+                    (_, Ok(value)) => Ok(Toml::Float(value)),
+                    _ => Err(format!("invalid value `{}`", word)),
+                },
+            }
+        }
+        Some(ch) => Err(format!("unexpected character `{}`", ch)),
+        None => Err("unexpected end of file".to_string()),
+    }
+}
+
+fn parse_array(chars: &mut TChars) -> Result<Toml, String> {
+    chars.next();
+
+    let mut items = Vec::new();
+    loop {
+        chars.skip_blank();
+        if chars.peek() == Some(']') {
+            chars.next();
+            return Ok(Toml::Array(items));
+        }
+        items.push(parse_value(chars)?);
+
+        chars.skip_blank();
+        match chars.next() {
+            Some(',') => continue,
+            Some(']') => return Ok(Toml::Array(items)),
+            _ => return Err("expected `,` or `]`".to_string()),
+        }
+    }
+}
+
+fn parse_hex(chars: &mut TChars, len: usize) -> Result<char, String> {
+    let hex: String = (0..len).filter_map(|_| chars.next()).collect();
+    match u32::from_str_radix(&hex, 16).ok().and_then(std::char::from_u32) {
+        Some(ch) if hex.len() == len => Ok(ch),
+        _ => Err(format!("invalid escape sequence `{}`", hex)),
+    }
+}
+
+fn parse_basic_string(chars: &mut TChars) -> Result<String, String> {
+    chars.next();
+
+    let mut result = String::new();
+    loop {
+        if let Some('\n') | None = chars.peek() {
+            return Err("unterminated string".to_string());
+        }
+        match chars.next() {
+            Some('"') if result.is_empty() && chars.peek() == Some('"') => {
+                return Err("multi-line strings are not supported".to_string())
+            }
+            Some('"') => return Ok(result),
+            Some('\\') => match chars.next() {
+                Some('b') => result.push('\u{8}'),
+                Some('t') => result.push('\t'),
+                Some('n') => result.push('\n'),
+                Some('f') => result.push('\u{c}'),
+                Some('r') => result.push('\r'),
+                Some('"') => result.push('"'),
+                Some('\\') => result.push('\\'),
+                Some('u') => result.push(parse_hex(chars, 4)?),
+                Some('U') => result.push(parse_hex(chars, 8)?),
+                Some(ch) => return Err(format!("invalid escape sequence `\\{}`", ch)),
+                None => return Err("unexpected end of file".to_string()),
+            },
+            Some(ch) => result.push(ch),
+            None => return Err("unexpected end of file".to_string()),
+        }
+    }
+}
+
+fn parse_literal_string(chars: &mut TChars) -> Result<String, String> {
+    chars.next();
+
+    let mut result = String::new();
+    loop {
+        if let Some('\n') | None = chars.peek() {
+            return Err("unterminated string".to_string());
+        }
+        match chars.next() {
+            Some('\'') => return Ok(result),
+            Some(ch) => result.push(ch),
+            None => return Err("unexpected end of file".to_string()),
+        }
+    }
+}
+
+#[test]
+fn test_parse() {
+    let src = "# comment\nmax_width = 80 # comment\n\"indentation_string\" = \"\\t\"\nfield_separator = ';'\n\n\
+               remove_comments = true\nratio = 1.5\nfiles = [\n  \"a\", # first\n  'b',\n]\n";
+    assert_eq!(
+        parse(src),
+        Ok(vec![
+            ("max_width".to_string(), Toml::Integer(80), 2),
+            ("indentation_string".to_string(), Toml::String("\t".to_string()), 3),
+            ("field_separator".to_string(), Toml::String(";".to_string()), 4),
+            ("remove_comments".to_string(), Toml::Bool(true), 6),
+            ("ratio".to_string(), Toml::Float(1.5), 7),
+            ("files".to_string(), Toml::Array(vec![Toml::String("a".to_string()), Toml::String("b".to_string())]), 8),
+        ])
+    );
+
+    assert_eq!(parse(""), Ok(vec![]));
+    assert_eq!(parse("a = 1\nb = 2 3\n"), Err("unexpected character `3` after the value at line 2".to_string()));
+    assert_eq!(parse("a = 1\na = 2\n"), Err("duplicate key `a` at line 2".to_string()));
+    assert_eq!(parse("a = 1\n[a]\n"), Err("duplicate key `a` at line 2".to_string()));
+    assert_eq!(parse("a = \"b\n"), Err("unterminated string at line 1".to_string()));
+    assert_eq!(parse("a = \"\\u00e9\\U0001F600\""), Ok(vec![("a".to_string(), Toml::String("é😀".to_string()), 1)]));
+    assert!(parse("a = yes").is_err());
+    assert!(parse("a.b = 1").is_err());
+}
+
+#[test]
+fn test_parse_tables() {
+    let src = "a = 1\n\n[[overrides]]\nfiles = \"spec/**\"\nmax_width = 140\n\n[table] # comment\nb = true\n\n\
+               [[overrides]]\nfiles = [\"gen/*.lua\"]\n";
+    let table = |pairs: Vec<(&str, Toml, usize)>| {
+        Toml::Table(pairs.into_iter().map(|(key, value, line)| (key.to_string(), value, line)).collect())
+    };
+    assert_eq!(
+        parse(src),
+        Ok(vec![
+            ("a".to_string(), Toml::Integer(1), 1),
+            (
+                "overrides".to_string(),
+                Toml::Array(vec![
+                    table(vec![
+                        ("files", Toml::String("spec/**".to_string()), 4),
+                        ("max_width", Toml::Integer(140), 5)
+                    ]),
+                    table(vec![("files", Toml::Array(vec![Toml::String("gen/*.lua".to_string())]), 11)]),
+                ]),
+                3
+            ),
+            ("table".to_string(), table(vec![("b", Toml::Bool(true), 8)]), 7),
+        ])
+    );
+
+    assert_eq!(parse("[a]\nb = 1\n[a]\n"), Err("duplicate key `a` at line 3".to_string()));
+    assert_eq!(parse("[[a]]\nb = 1\nb = 2\n"), Err("duplicate key `b` at line 3".to_string()));
+    assert_eq!(parse("[a\n"), Err("expected `]` after the table name at line 2".to_string()));
+    assert!(parse("[[a]] b = 1").is_err());
+}
@@ -0,0 +1,102 @@
+extern crate luafmt;
+use luafmt::ast::{self, Dialect, Loc, Node, Visitor, VisitorMut};
+
+// the calls with the spans of their function names
+struct Calls<'a> {
+    src: &'a str,
+    calls: Vec<(String, usize, usize)>,
+}
+
+impl Visitor for Calls<'_> {
+    fn visit_node(&mut self, node: &Node) {
+        if let Node::Var(_, _, name, suffixes) = node {
+            if let (Node::Name(Loc(l, r), _), Node::VarSuffixList(_, items)) = (&**name, &**suffixes) {
+                if let Some((_, Node::ArgsRoundBrackets(..))) | Some((_, Node::ArgsRoundBracketsEmpty(..))) =
+                    items.first()
+                {
+                    let text: String = self.src.chars().skip(*l).take(r - l).collect();
+                    self.calls.push((text, *l, *r));
+                }
+            }
+        }
+        ast::walk(self, node);
+    }
+}
+
+#[test]
+fn test_parse_and_visit() {
+    let src = "local a = f('ё')\nif a then\n  print(g(), a)\nend\n";
+    let tree = ast::parse(src, Dialect::Lua54).unwrap();
+    match &tree {
+        Node::Chunk(_, block, Loc(l, r)) => assert_eq!((block.loc().0, block.loc().1, *l, *r), (0, 46, 46, 47)),
+        _ => panic!("{:?}", tree),
+    }
+
+    let mut visitor = Calls { src, calls: Vec::new() };
+    visitor.visit_node(&tree);
+    assert_eq!(
+        visitor.calls,
+        vec![("f".to_string(), 10, 11), ("print".to_string(), 29, 34), ("g".to_string(), 35, 36)]
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    let err = ast::parse("local a = = 1", Dialect::Lua54).unwrap_err();
+    assert_eq!((err.line, err.column), (1, 11));
+
+    // the dialect changes the accepted syntax
+    assert!(ast::parse("a += 1", Dialect::Lua54).is_err());
+    assert!(ast::parse("a += 1", Dialect::Luau).is_ok());
+
+    let (tree, errors) = ast::parse_with_recovery("a = 1\nb = = 2\nc = 3\n", Dialect::Lua54).unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 2);
+
+    let mut unparsed = 0;
+    struct Unparsed<'a>(&'a mut usize);
+    impl Visitor for Unparsed<'_> {
+        fn visit_node(&mut self, node: &Node) {
+            if let Node::Unparsed(..) = node {
+                *self.0 += 1;
+            }
+            ast::walk(self, node);
+        }
+    }
+    Unparsed(&mut unparsed).visit_node(&tree);
+    assert_eq!(unparsed, 1);
+}
+
+#[test]
+fn test_children_and_visit_mut() {
+    let mut tree = ast::parse("local a, b = 1, c", Dialect::Lua54).unwrap();
+
+    // the chunk holds the statement list with the single statement
+    let stat = tree.children().next().unwrap().children().next().unwrap();
+    let names: Vec<_> = stat.children().map(|n| n.children().count()).collect();
+    assert_eq!(names, [2, 2]);
+
+    struct Rename;
+    impl VisitorMut for Rename {
+        fn visit_node_mut(&mut self, node: &mut Node) {
+            if let Node::Name(_, name) = node {
+                name.make_ascii_uppercase();
+            }
+            ast::walk_mut(self, node);
+        }
+    }
+    Rename.visit_node_mut(&mut tree);
+
+    struct Names(Vec<String>);
+    impl Visitor for Names {
+        fn visit_node(&mut self, node: &Node) {
+            if let Node::Name(_, name) = node {
+                self.0.push(name.clone());
+            }
+            ast::walk(self, node);
+        }
+    }
+    let mut names = Names(Vec::new());
+    names.visit_node(&tree);
+    assert_eq!(names.0, ["A", "B", "C"]);
+}
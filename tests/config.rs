@@ -31,6 +31,7 @@ fn test_load_from_file() {
             write_trailing_field_separator: Some(true),
             ..FormatOpts::default()
         },
+        root: Some(true),
         ..Config::default()
     };
 
@@ -48,3 +49,242 @@ fn test_load_from_file() {
     assert_eq!(actual, Config::default());
     assert!(actual.has_empty_format());
 }
+
+#[test]
+fn test_with_style() {
+    let mut cfg = Config::default();
+    cfg.set("style", "compact");
+    cfg.set("max_width", "80");
+    assert_eq!(cfg.style, Some("compact".to_string()));
+    assert!(!cfg.has_empty_format());
+
+    let actual = cfg.with_style();
+    assert_eq!(actual.fmt.indentation_string, Some("  ".to_string()));
+    assert_eq!(actual.fmt.max_width, Some(80));
+    assert_eq!(actual.fmt.field_separator, Some(",".to_string()));
+
+    // the unknown style is not set
+    let mut cfg = Config::default();
+    cfg.set("style", "unknown");
+    assert_eq!(cfg, Config::default());
+    assert_eq!(cfg.with_style(), Config::default());
+}
+
+#[test]
+fn test_load_from_toml_and_json() {
+    let expected = Config {
+        style: Some("compact".to_string()),
+        fmt: FormatOpts {
+            indentation_string: Some("\t".to_string()),
+            max_width: Some(80),
+            remove_comments: Some(true),
+            ..FormatOpts::default()
+        },
+        ..Config::default()
+    };
+
+    let cfg_path_buf = Path::new("tests/config_files/valid.toml").to_path_buf();
+    assert_eq!(Config::default().reload_format_from_file(&cfg_path_buf), Ok(expected.clone()));
+
+    let cfg_path_buf = Path::new("tests/config_files/valid.json").to_path_buf();
+    assert_eq!(Config::default().reload_format_from_file(&cfg_path_buf), Ok(expected));
+}
+
+#[test]
+fn test_load_invalid_file() {
+    let cfg_path_buf = Path::new("tests/config_files/invalid.toml").to_path_buf();
+    assert_eq!(
+        Config::default().reload_format_from_file(&cfg_path_buf),
+        Err([
+            "tests/config_files/invalid.toml:1: Invalid option name `max_widht`, did you mean `max_width`?",
+            "tests/config_files/invalid.toml:2: Invalid `indentation_string` option type, expected string, found integer",
+            "tests/config_files/invalid.toml:4: Invalid `remove_comments` option type, expected boolean, found string",
+        ]
+        .join("\n"))
+    );
+
+    let cfg_path_buf = Path::new("tests/config_files/invalid.lua").to_path_buf();
+    assert_eq!(
+        Config::default().reload_format_from_file(&cfg_path_buf),
+        Err([
+            "tests/config_files/invalid.lua:2: Invalid option name `max_widht`, did you mean `max_width`?",
+            "tests/config_files/invalid.lua:3: Invalid `indentation_string` option type, expected string, found integer",
+            "tests/config_files/invalid.lua:4: Invalid statement, expected `name = value`",
+            "tests/config_files/invalid.lua:5: Invalid `remove_comments` option type, expected boolean, found expression",
+        ]
+        .join("\n"))
+    );
+
+    let cfg_path_buf = Path::new("tests/config_files/missing.toml").to_path_buf();
+    assert!(Config::default().reload_format_from_file(&cfg_path_buf).is_err());
+}
+
+#[test]
+fn test_load_overrides() {
+    use luafmt::config::ConfigOverride;
+    use luafmt::glob::Glob;
+
+    let base = Path::new("tests/config_files").to_path_buf();
+    let expected = Config {
+        fmt: FormatOpts { max_width: Some(100), ..FormatOpts::default() },
+        overrides: vec![
+            ConfigOverride {
+                files: vec![Glob::new("spec/**/*.lua").unwrap()],
+                base: base.clone(),
+                config: Config {
+                    fmt: FormatOpts { max_width: Some(140), ..FormatOpts::default() },
+                    ..Config::default()
+                },
+            },
+            ConfigOverride {
+                files: vec![Glob::new("vendor/").unwrap(), Glob::new("*_gen.lua").unwrap()],
+                base: base.clone(),
+                config: Config {
+                    fmt: FormatOpts { remove_comments: Some(true), ..FormatOpts::default() },
+                    ..Config::default()
+                },
+            },
+        ],
+        ..Config::default()
+    };
+
+    for file_name in &["overrides.toml", "overrides.json", "overrides.lua"] {
+        let cfg_path_buf = base.join(file_name);
+        assert_eq!(Config::default().reload_format_from_file(&cfg_path_buf), Ok(expected.clone()), "{}", file_name);
+    }
+
+    let options = |path: &str| {
+        let cfg = expected.for_file(&base.join(path));
+        assert!(cfg.overrides.is_empty());
+        (cfg.fmt.max_width, cfg.fmt.remove_comments)
+    };
+    assert_eq!(options("main.lua"), (Some(100), None));
+    assert_eq!(options("spec/dir/main_spec.lua"), (Some(140), None));
+    assert_eq!(options("spec/vendor/lib_gen.lua"), (Some(140), Some(true)));
+    assert_eq!(options("vendor/lib.lua"), (Some(100), Some(true)));
+    assert_eq!(options("../main_gen.lua"), (Some(100), None));
+
+    let cfg_path_buf = base.join("invalid_overrides.toml");
+    assert_eq!(
+        Config::default().reload_format_from_file(&cfg_path_buf),
+        Err([
+            "tests/config_files/invalid_overrides.toml:2: The override section has no `files` option",
+            "tests/config_files/invalid_overrides.toml:5: Invalid `files` item type, expected string, found integer",
+            "tests/config_files/invalid_overrides.toml:6: The `root` option is not supported in the override section",
+            "tests/config_files/invalid_overrides.toml:7: Invalid option name `max_widht`, did you mean `max_width`?",
+        ]
+        .join("\n"))
+    );
+}
+
+#[test]
+fn test_documented_config() {
+    use luafmt::config::{documented_config, FORMAT_OPTIONS};
+    use luafmt::style;
+    use std::fs;
+
+    let content = documented_config();
+    for option in FORMAT_OPTIONS.iter() {
+        assert!(content.contains(&format!("{} = ", option.name)), "{}", option.name);
+    }
+
+    // the documented configuration is loadable and equal to the default style
+    let cfg_path_buf = Path::new(env!("CARGO_TARGET_TMPDIR")).join("documented.luafmt.lua");
+    fs::write(&cfg_path_buf, &content).unwrap();
+    let expected = Config { fmt: style::preset("default").unwrap(), ..Config::default() };
+    assert_eq!(Config::default().reload_format_from_file(&cfg_path_buf), Ok(expected));
+}
+
+#[test]
+fn test_string_escapes() {
+    use luafmt::config::lua_config_with_sources;
+    use std::fs;
+
+    let fmt = FormatOpts {
+        indentation_string: Some("\t".to_string()),
+        hint_before_comment: Some("'\"\\n\r\n\u{7}1".to_string()),
+        hint_table_constructor: Some("é—\u{85}\\".to_string()),
+        ..FormatOpts::default()
+    };
+    let cfg = Config { line_range: Some((1, 2)), fmt, ..Config::default() };
+
+    // the printed strings are loaded back unchanged
+    let content = lua_config_with_sources(&[("test".to_string(), cfg.clone())]);
+    assert!(content.contains("hint_before_comment = \"'\\\"\\\\n\\r\\n\\u{7}1\" -- test\n"), "{}", content);
+    let cfg_path_buf = Path::new(env!("CARGO_TARGET_TMPDIR")).join("escapes.luafmt.lua");
+    fs::write(&cfg_path_buf, &content).unwrap();
+    assert_eq!(Config::default().reload_format_from_file(&cfg_path_buf), Ok(cfg));
+
+    // the escape sequences of the Lua files are processed
+    let content = "indentation_string = '\\x20\\32\\u{20}\\z\n  \\t'\nhint_before_comment = \"\\q\"\n";
+    fs::write(&cfg_path_buf, content).unwrap();
+    let fmt = FormatOpts {
+        indentation_string: Some("   \t".to_string()),
+        hint_before_comment: Some("\\q".to_string()),
+        ..FormatOpts::default()
+    };
+    assert_eq!(Config::default().reload_format_from_file(&cfg_path_buf), Ok(Config { fmt, ..Config::default() }));
+
+    // the byte escapes are decoded as UTF-8
+    let content = "hint_before_comment = \"\\226\\128\\148\"\nhint_table_constructor = '\\xE2\\x80\\x94'\n";
+    fs::write(&cfg_path_buf, content).unwrap();
+    let fmt = FormatOpts {
+        hint_before_comment: Some("—".to_string()),
+        hint_table_constructor: Some("—".to_string()),
+        ..FormatOpts::default()
+    };
+    assert_eq!(Config::default().reload_format_from_file(&cfg_path_buf), Ok(Config { fmt, ..Config::default() }));
+
+    fs::write(&cfg_path_buf, "hint_before_comment = \"\\226\\128\"\n").unwrap();
+    assert_eq!(
+        Config::default().reload_format_from_file(&cfg_path_buf),
+        Err(format!("{}:1: Invalid string, its escape sequences are not valid UTF-8", cfg_path_buf.display()))
+    );
+}
+
+#[test]
+fn test_option_registry() {
+    use luafmt::config::{all_options, OptionValue, FORMAT_OPTIONS};
+
+    // every format option is set, printed and merged
+    let mut cfg = Config::default();
+    for option in FORMAT_OPTIONS.iter() {
+        let value = match option.type_name {
+            "boolean" => "true",
+            "string" => ",",
+            _ => "1",
+        };
+        assert_eq!(cfg.try_set(option.name, &OptionValue::Text(value.to_string())), Ok(()), "{}", option.name);
+    }
+    let names: Vec<_> = cfg.option_values().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, FORMAT_OPTIONS.iter().map(|option| option.name).collect::<Vec<_>>());
+
+    let mut merged = Config::default();
+    merged.merge(&cfg);
+    assert_eq!(merged, cfg);
+
+    let mut names: Vec<_> = all_options().map(|option| option.name).collect();
+    names.sort_unstable();
+    names.dedup();
+    assert_eq!(names.len(), all_options().count());
+
+    // the values are validated against the domains of the options
+    let mut cfg = Config::default();
+    assert_eq!(
+        cfg.try_set("newline_format_if", &OptionValue::Integer(2)),
+        Err("Invalid `newline_format_if` option value `2`, expected one of: 1".to_string())
+    );
+    assert_eq!(cfg.try_set("newline_format_binary_op", &OptionValue::Integer(2)), Ok(()));
+    assert_eq!(
+        cfg.try_set("field_separator", &OptionValue::Text(":".to_string())),
+        Err("Invalid `field_separator` option value `:`, expected one of: \",\", \";\"".to_string())
+    );
+    assert_eq!(
+        cfg.try_set("max_width", &OptionValue::Text("0".to_string())),
+        Err("Invalid `max_width` option value `0`, expected positive integer".to_string())
+    );
+    assert_eq!(
+        cfg.try_set("max_widht", &OptionValue::Integer(80)),
+        Err("Invalid option name `max_widht`, did you mean `max_width`?".to_string())
+    );
+}
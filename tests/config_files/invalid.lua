@@ -0,0 +1,6 @@
+-- misspelled name
+max_widht = 80
+indentation_string = 4
+print("statement")
+remove_comments = not false
+field_separator = ","
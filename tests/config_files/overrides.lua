@@ -0,0 +1,6 @@
+max_width = 100
+
+overrides = {
+    { files = "spec/**/*.lua", max_width = 140 },
+    { files = { "vendor/", "*_gen.lua" }, remove_comments = true },
+}
@@ -47,7 +47,7 @@ fn test_get_path_files() {
 #[test]
 fn test_get_file_config() {
     let path_buf = Path::new("tests/scripts1/file1.lua").to_path_buf();
-    let actual = get_file_config(&path_buf, CFG_PREFIX);
+    let actual = get_file_config(&path_buf, CFG_PREFIX).unwrap();
     assert!(
         actual.as_ref().unwrap().ends_with("tests/scripts1/.luafmt.lua"),
         "Actual path: `{}`",
@@ -55,7 +55,7 @@ fn test_get_file_config() {
     );
 
     let path_buf = Path::new("tests/scripts1/subdir1/file3.lua").to_path_buf();
-    let actual = get_file_config(&path_buf, CFG_PREFIX);
+    let actual = get_file_config(&path_buf, CFG_PREFIX).unwrap();
     assert!(
         actual.as_ref().unwrap().ends_with("tests/scripts1/.luafmt.lua"),
         "Actual path: `{}`",
@@ -63,10 +63,110 @@ fn test_get_file_config() {
     );
 
     let path_buf = Path::new("tests/scripts1/subdir1/subdir2/file4.lua").to_path_buf();
-    let actual = get_file_config(&path_buf, CFG_PREFIX);
+    let actual = get_file_config(&path_buf, CFG_PREFIX).unwrap();
     assert!(
         actual.as_ref().unwrap().ends_with("tests/scripts1/subdir1/subdir2/.luafmt_inner.lua"),
         "Actual path: `{}`",
         actual.as_ref().unwrap().display()
     );
+
+    // the configuration files of one directory conflict
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("conflicting_configs");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("sub")).unwrap();
+    std::fs::write(root.join(".luafmt.toml"), "root = true\n").unwrap();
+    std::fs::write(root.join(".luafmt.lua"), "root = true\n").unwrap();
+    std::fs::write(root.join("sub/file.lua"), "").unwrap();
+    let actual = get_file_config(&root.join("sub/file.lua"), CFG_PREFIX).map_err(|err| err.to_string());
+    let expected = format!("Conflicting configuration files in `{}`: `.luafmt.toml`, `.luafmt.lua`", root.display());
+    assert_eq!(actual, Err(expected));
+
+    // the parent directories are not read after the nearest file
+    std::fs::write(root.join("sub/.luafmt.json"), "{}").unwrap();
+    let actual = get_file_config(&root.join("sub/file.lua"), CFG_PREFIX).unwrap();
+    assert_eq!(actual, Some(root.join("sub/.luafmt.json").canonicalize().unwrap()));
+}
+
+#[test]
+fn test_get_filtered_path_files() {
+    use luafmt::glob::Glob;
+    use std::fs;
+
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("filtered_path_files");
+    let _ = fs::remove_dir_all(&root);
+    for dir in &["src/gen", "vendor/lib", "build"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    for file in &["a.lua", "src/b.lua", "src/c_gen.lua", "src/gen/d.lua", "vendor/lib/e.lua", "build/f.lua"] {
+        fs::write(root.join(file), "local a = 1\n").unwrap();
+    }
+    // the invalid pattern is skipped with a warning
+    fs::write(root.join(".gitignore"), "# build output\n/build/\n[z-a]\n*_gen.lua\n").unwrap();
+    fs::write(root.join(".luafmtignore"), "vendor\n").unwrap();
+    fs::write(root.join("src/.luafmtignore"), "gen/\n!c_gen.lua\n").unwrap();
+
+    let get_files = |filter: &FileFilter| {
+        let mut actual = get_filtered_path_files(&root, true, &["lua"], CFG_PREFIX, filter).ok().unwrap_or(vec![]);
+        actual.sort();
+        actual.iter().map(|path| path.strip_prefix(&root).unwrap().to_path_buf()).collect::<Vec<_>>()
+    };
+    let paths = |files: &[&str]| files.iter().map(|file| Path::new(file).to_path_buf()).collect::<Vec<_>>();
+
+    assert_eq!(get_files(&FileFilter::default()).len(), 6);
+
+    let filter = FileFilter { ignore_files: true, ..FileFilter::default() };
+    assert_eq!(get_files(&filter), paths(&["a.lua", "src/b.lua", "src/c_gen.lua"]));
+
+    let filter =
+        FileFilter { ignore_files: true, excludes: vec![Glob::new("src/b.lua").unwrap()], ..FileFilter::default() };
+    assert_eq!(get_files(&filter), paths(&["a.lua", "src/c_gen.lua"]));
+
+    let filter = FileFilter {
+        ignore_files: true,
+        excludes: vec![Glob::new("!vendor/").unwrap()],
+        includes: vec![Glob::new("vendor").unwrap(), Glob::new("a.lua").unwrap()],
+        ..FileFilter::default()
+    };
+    assert_eq!(get_files(&filter), paths(&["a.lua", "vendor/lib/e.lua"]));
+}
+
+#[test]
+fn test_lua_shebang() {
+    use std::fs;
+
+    assert!(is_lua_shebang("#!/usr/bin/lua\n"));
+    assert!(is_lua_shebang("#!/usr/local/bin/luajit -joff"));
+    assert!(is_lua_shebang("#!/usr/bin/env lua5.1"));
+    assert!(is_lua_shebang("#!/usr/bin/env -S LUA_INIT= lua -W"));
+    assert!(!is_lua_shebang("#!/bin/sh"));
+    assert!(!is_lua_shebang("#!/usr/bin/env python3"));
+    assert!(!is_lua_shebang("-- lua"));
+
+    assert_eq!(shebang_lua_version("#!/usr/local/bin/luajit -joff"), Some("luajit"));
+    assert_eq!(shebang_lua_version("#!/usr/bin/env luajit-2.1"), Some("luajit"));
+    assert_eq!(shebang_lua_version("#!/usr/bin/env lua5.1"), Some("5.1"));
+    assert_eq!(shebang_lua_version("#!/usr/bin/lua53"), Some("5.3"));
+    assert_eq!(shebang_lua_version("#!/usr/bin/env luau"), Some("luau"));
+    assert_eq!(shebang_lua_version("#!/usr/bin/lua"), None);
+    assert_eq!(shebang_lua_version("#!/bin/sh"), None);
+
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("lua_shebang");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("bin")).unwrap();
+    fs::write(root.join("bin/tool"), "#!/usr/bin/env lua\nprint(1)\n").unwrap();
+    fs::write(root.join("bin/script"), "#!/bin/sh\necho 1\n").unwrap();
+    fs::write(root.join("bin/binary"), [0x7f, b'E', b'L', b'F', 0xff, 0xfe]).unwrap();
+    fs::write(root.join("a.lua"), "print(1)\n").unwrap();
+    fs::write(root.join("b.luau"), "print(1)\n").unwrap();
+    fs::write(root.join("c.rockspec"), "package = 'c'\n").unwrap();
+
+    let get_files = |exts: &[&str], filter: &FileFilter| {
+        let mut actual = get_filtered_path_files(&root, true, exts, CFG_PREFIX, filter).ok().unwrap_or(vec![]);
+        actual.sort();
+        actual.iter().map(|path| path.strip_prefix(&root).unwrap().to_string_lossy().to_string()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(get_files(&["lua"], &FileFilter::default()), vec!["a.lua"]);
+    let filter = FileFilter { lua_shebang: true, ..FileFilter::default() };
+    assert_eq!(get_files(&["lua", "luau", "rockspec"], &filter), vec!["a.lua", "b.luau", "bin/tool", "c.rockspec"]);
 }
@@ -121,3 +121,245 @@ fn test_process_file_failure() {
         _ => false,
     });
 }
+
+#[test]
+fn test_process_file_parsing_error() {
+    use FormatterError::*;
+
+    let path_buf = Path::new("tests/scripts_err/error.lua").to_path_buf();
+    let config = Config::default();
+    match process_file(&path_buf, &config, false) {
+        Err(ParsingError(err)) => {
+            assert_eq!((err.line, err.column), (7, 23));
+            assert_eq!(err.expected, vec!["end"]);
+            assert_eq!(err.file_name, Some("tests/scripts_err/error.lua".to_string()));
+            assert_eq!(
+                format!("{}", err),
+                "unexpected end of file, expected `end`\n --> tests/scripts_err/error.lua:7:23\n  |\n7 | -- no `end` at the end\n  |                       ^"
+            );
+        }
+        actual => panic!("{:?}", actual),
+    }
+}
+
+#[test]
+fn test_process_buffer_to_edits() {
+    use luafmt::config::FormatOpts;
+
+    let config = Config {
+        fmt: FormatOpts {
+            replace_zero_spaces_with_hint: Some(true),
+            remove_spaces_between_tokens: Some(true),
+            hint_before_comment: Some(" ".to_string()),
+            ..FormatOpts::default()
+        },
+        ..Config::default()
+    };
+    let content = "local  a=1 -- comment\nprint( a )\n".to_string();
+    let actual = process_buffer_to_edits(&content, &config, false);
+
+    assert!(actual.as_ref().ok().is_some(), "{:?}", actual);
+    assert_eq!(
+        actual.unwrap(),
+        vec![
+            (6..7, String::new()),
+            (8..8, " ".to_string()),
+            (9..9, " ".to_string()),
+            (28..29, String::new()),
+            (30..31, String::new()),
+        ]
+    );
+
+    // the tokens of the dialect are matched, the interpolated string is a token of Luau
+    let config = Config { lua_version: Some("luau".to_string()), ..config };
+    let content = "local s=`{a}`\nx=1".to_string();
+    assert_eq!(
+        process_buffer_to_edits(&content, &config, false).unwrap(),
+        vec![(7..7, " ".to_string()), (8..8, " ".to_string()), (15..15, " ".to_string()), (16..16, " ".to_string())]
+    );
+}
+
+#[test]
+fn test_get_file_config() {
+    use luafmt::config::FormatOpts;
+
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("file_config");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("sub/inner")).unwrap();
+    let root_config = "root = true\nindentation_string = \"  \"\nmax_width = 80\n\
+                       overrides = { { files = \"inner/\", indentation_string = \"   \", max_width = 60 } }\n";
+    fs::write(root.join(".luafmt.lua"), root_config).unwrap();
+    fs::write(root.join("sub/.luafmt.lua"), "indentation_string = \"\t\"\n").unwrap();
+    fs::write(root.join("sub/inner/file.lua"), "if a then\nb()\nend\n").unwrap();
+
+    // the overrides of the parent file do not override the options of the nearer file
+    let path_buf = root.join("sub/inner/file.lua");
+    let expected = Config {
+        root: Some(true),
+        fmt: FormatOpts { indentation_string: Some("\t".to_string()), max_width: Some(60), ..FormatOpts::default() },
+        ..Config::default()
+    };
+    assert_eq!(get_file_config(&path_buf).ok(), Some(Some(expected)));
+
+    // the command line options override the options of the files
+    let fmt = FormatOpts {
+        newline_format_if: Some(1),
+        newline_format_statement: Some(1),
+        indentation_string: Some("    ".to_string()),
+        ..FormatOpts::default()
+    };
+    let actual = process_file(&path_buf, &Config { fmt, ..Config::default() }, false);
+    assert_eq!(actual.ok(), Some("if a then\n    b()\nend\n".to_string()));
+
+    let fmt = FormatOpts { newline_format_if: Some(1), newline_format_statement: Some(1), ..FormatOpts::default() };
+    let actual = process_file(&path_buf, &Config { fmt, ..Config::default() }, false);
+    assert_eq!(actual.ok(), Some("if a then\n\tb()\nend\n".to_string()));
+
+    // the parent files are not read above the root one
+    let path_buf = Path::new("tests/scripts1/file1.lua").to_path_buf();
+    let actual = get_file_config(&path_buf).ok().and_then(|cfg| cfg).and_then(|cfg| cfg.fmt.max_width);
+    assert_eq!(actual, None);
+
+    // the search stops at the root file before the conflicting files of the parent directory
+    fs::write(root.join(".luafmt.toml"), "max_width = 100\n").unwrap();
+    fs::write(root.join("sub/.luafmt.lua"), "root = true\nmax_width = 90\n").unwrap();
+    let path_buf = root.join("sub/inner/file.lua");
+    let actual = get_file_config(&path_buf).ok().and_then(|cfg| cfg).and_then(|cfg| cfg.fmt.max_width);
+    assert_eq!(actual, Some(90));
+
+    fs::write(root.join("sub/.luafmt.lua"), "max_width = 90\n").unwrap();
+    assert!(matches!(get_file_config(&path_buf), Err(FormatterError::InvalidConfigFile(..))));
+}
+
+#[test]
+fn test_print_file_config() {
+    use luafmt::config::FormatOpts;
+
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("print_config");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("spec")).unwrap();
+    let root_config = "root = true\nstyle = \"minified\"\nmax_width = 100\n\n\
+                       [[overrides]]\nfiles = \"spec/\"\nmax_width = 140\nfield_separator = ';'\n";
+    fs::write(root.join(".luafmt.toml"), root_config).unwrap();
+    fs::write(root.join("spec/file.lua"), "local a = 1\n").unwrap();
+
+    let path_buf = root.join("spec/file.lua");
+    let cfg = Config { fmt: FormatOpts { remove_comments: Some(false), ..FormatOpts::default() }, ..Config::default() };
+    let actual = print_file_config(&path_buf, &cfg).unwrap();
+    let config_name = root.join(".luafmt.toml").display().to_string();
+    let expected = [
+        format!("-- the effective configuration of `{}`", path_buf.display()),
+        "replace_zero_spaces_with_hint = true -- style `minified`".to_string(),
+        "remove_comments = false -- command line".to_string(),
+        "remove_all_newlines = true -- style `minified`".to_string(),
+        "remove_spaces_between_tokens = true -- style `minified`".to_string(),
+        "remove_optional_spaces = true -- style `minified`".to_string(),
+        format!("field_separator = \";\" -- {}, override `spec/`", config_name),
+        format!("max_width = 140 -- {}, override `spec/`", config_name),
+        format!("root = true -- {}", config_name),
+        format!("style = \"minified\" -- {}", config_name),
+    ];
+    assert_eq!(actual, expected.join("\n") + "\n");
+
+    // the printed configuration is loadable and equal to the resolved one
+    fs::write(root.join("printed.lua"), &actual).unwrap();
+    let printed = Config::default().reload_format_from_file(&root.join("printed.lua"));
+    let mut resolved = get_file_config(&path_buf).unwrap().unwrap();
+    resolved.merge(&cfg);
+    assert_eq!(printed.map(|cfg| cfg.with_style()), Ok(resolved.with_style()));
+}
+
+#[test]
+fn test_verify_output() {
+    let config = Config { style: Some("roblox-like".to_string()), verify: Some(true), ..Config::default() };
+    let content = "local t = {1; 'a\"b', f = function( x ) return x; end}\nif t then print( t ) end\n".to_string();
+    let actual = process_buffer_with_config(&content, &config, false);
+    assert!(actual.as_ref().ok().is_some(), "{:?}", actual);
+
+    let config = config.with_style();
+    let output = actual.unwrap();
+    assert!(verify_output(&content, &output, &config).is_ok());
+
+    // the output which means something else
+    let changed = output.replace("print(t)", "print(t)()");
+    match verify_output(&content, &changed, &config) {
+        Err(FormatterError::VerificationError(msg)) => assert_eq!(
+            msg,
+            "the syntax tree of the output differs, `VarSuffixList[1]` at line 2 became `VarSuffixList[2]` at line 3"
+        ),
+        actual => panic!("{:?}", actual),
+    }
+
+    // the output which is changed by the second formatting
+    let unformatted = output.replace("print(t)", "print( t )");
+    match verify_output(&content, &unformatted, &config) {
+        Err(FormatterError::VerificationError(msg)) => {
+            assert_eq!(msg, "the second formatting changes the output at line 3")
+        }
+        actual => panic!("{:?}", actual),
+    }
+
+    match verify_output(&content, "local t = {", &config) {
+        Err(FormatterError::VerificationError(msg)) => {
+            assert!(msg.starts_with("the output cannot be parsed"), "{}", msg)
+        }
+        actual => panic!("{:?}", actual),
+    }
+}
+
+#[test]
+fn test_minified_style() {
+    let config = Config { style: Some("minified".to_string()), verify: Some(true), ..Config::default() };
+    let content = "-- comment\nlocal t = { a = 1, b = {2} }\nlocal s = 'a' .. \"b\" -- comment\n\
+                   if s == t.a then f(t, s) end\nlocal function g(x) return -x + 1 .. 2 - -x end\n"
+        .to_string();
+    let actual = process_buffer_with_config(&content, &config, false);
+    assert_eq!(
+        actual.unwrap(),
+        "local t={a=1,b={2}}local s='a'..\"b\"if s==t.a then f(t,s)end local function g(x)return-x+1 .. 2- -x end"
+    );
+}
+
+#[test]
+fn test_process_markdown() {
+    let config = Config { style: Some("default".to_string()), ..Config::default() };
+    let content = "# Usage\n\n* item\n\n  ```lua\n  local  a=f( 1 )\n  ```\n\n```sh\nf( 1 )\n```\n".to_string();
+    let actual = process_markdown_with_config(&content, &config, false);
+    assert!(actual.as_ref().ok().is_some(), "{:?}", actual);
+    assert_eq!(actual.unwrap(), "# Usage\n\n* item\n\n  ```lua\n  local a = f(1)\n  ```\n\n```sh\nf( 1 )\n```\n");
+
+    let content = "Text\n\n  ```lua\n  local a = 1\n  local = 2\n  ```\n".to_string();
+    match process_markdown_with_config(&content, &config, false) {
+        Err(FormatterError::ParsingError(err)) => {
+            assert_eq!((err.line, err.column, err.source_line.as_str()), (5, 9, "  local = 2"))
+        }
+        actual => panic!("{:?}", actual),
+    }
+}
+
+#[test]
+fn test_process_luau_file() {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join("luau_file");
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join(".luafmt.lua"), "root = true\nstyle = \"default\"\nverify = true\n").unwrap();
+    let content = "local  n:number=1\nn+=f( `{n}` )\n";
+    fs::write(root.join("file.luau"), content).unwrap();
+    fs::write(root.join("file.lua"), content).unwrap();
+
+    // the dialect is taken from the extension of the file
+    let actual = process_file(&root.join("file.luau"), &Config::default(), false);
+    assert_eq!(actual.ok(), Some("local n: number = 1\nn += f(`{n}`)\n".to_string()));
+
+    let actual = process_file(&root.join("file.lua"), &Config::default(), false);
+    assert!(matches!(actual, Err(FormatterError::ParsingError(..))), "{:?}", actual);
+
+    let config = Config { lua_version: Some("luau".to_string()), ..Config::default() };
+    let actual = process_file(&root.join("file.lua"), &config, false);
+    assert_eq!(actual.ok(), Some("local n: number = 1\nn += f(`{n}`)\n".to_string()));
+
+    // or from the interpreter of the shebang
+    fs::write(root.join("script"), format!("#!/usr/bin/env luau\n{}", content)).unwrap();
+    let actual = process_file(&root.join("script"), &Config::default(), false);
+    assert_eq!(actual.ok(), Some("#!/usr/bin/env luau\nlocal n: number = 1\nn += f(`{n}`)\n".to_string()));
+}
@@ -1,3 +1,6 @@
+-- do not merge with the configuration files of the parent directories
+root = true
+
 hint_after_multiline_comment = " "
 hint_after_multiline_comment_text = " "
 hint_before_comment = " "
@@ -1,3 +1,6 @@
+-- do not merge with the configuration files of the parent directories
+root = true
+
 replace_zero_spaces_with_hint = true
 hint_after_multiline_comment = " "
 hint_after_multiline_comment_text = " "
@@ -10,6 +13,7 @@ hint_table_constructor = " "
 remove_single_newlines = true
 -- remove_all_newlines = false
 remove_spaces_between_tokens = true
+-- remove_optional_spaces = false
 write_newline_at_eof = true
 
 -- indent